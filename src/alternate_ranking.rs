@@ -0,0 +1,88 @@
+use crate::error::Result;
+use crate::factory::Factory;
+use crate::model::bom::Bom;
+use crate::model::book::FilterableBook;
+use crate::model::full_book::FullBook;
+use crate::model::rate::Rate;
+use crate::problem_input::ProblemInput;
+use crate::Recipe;
+use crate::recipe_filter::RecipeFilter;
+use crate::recipe_filter::RecipeFilter::{AllOf, AnyOf, Not, NotAlternate, NotNamed};
+
+/// What an alternate brings when it is the only one unlocked
+pub struct AlternateValue {
+    pub recipe: Recipe,
+    //the decrease of the objective, or the increase of the scale of the targets when the production is maximized
+    pub objective_gain: f64,
+    pub resource_savings: f64,
+    pub building_savings: i64,
+}
+
+/// Solve the problem without any alternate, then with each alternate kept by the filter unlocked
+/// on its own. When the production is maximized, the alternates are ranked by the scale they reach
+pub fn rank_alternates(input: &ProblemInput, full_book: &FullBook) -> Result<Vec<AlternateValue>> {
+    let without_alternates = AllOf(vec![input.filter.clone(), NotAlternate]);
+    let baseline = solve_with(input, full_book, without_alternates.clone())?;
+    let baseline_scale = if input.maximize { Some(maximal_scale(input, full_book, without_alternates)?) } else { None };
+
+    let candidates = full_book.recipes().iter()
+        .filter(|r| r.alternate() && input.filter.matches(r));
+
+    let mut values = vec![];
+    for recipe in candidates {
+        let filter = AllOf(vec![input.filter.clone(), only_alternate(recipe)]);
+        let bom = solve_with(input, full_book, filter.clone())?;
+        let objective_gain = match baseline_scale {
+            Some(baseline_scale) => maximal_scale(input, full_book, filter)? - baseline_scale,
+            None => baseline.objective - bom.objective,
+        };
+
+        values.push(AlternateValue {
+            recipe: recipe.clone(),
+            objective_gain,
+            resource_savings: baseline.resource_usage() - bom.resource_usage(),
+            building_savings: baseline.building_count() as i64 - bom.building_count() as i64,
+        });
+    }
+
+    values.sort_by(|v1, v2| v2.objective_gain.total_cmp(&v1.objective_gain)
+        .then(v2.resource_savings.total_cmp(&v1.resource_savings))
+        .then(v2.building_savings.cmp(&v1.building_savings)));
+    Ok(values)
+}
+
+/// Keep the regular recipes and the given alternate
+fn only_alternate(alternate: &Recipe) -> RecipeFilter {
+    AnyOf(vec![NotAlternate, Not(Box::new(NotNamed(alternate.id().to_string())))])
+}
+
+fn solve_with(input: &ProblemInput, full_book: &FullBook, filter: RecipeFilter) -> Result<Bom> {
+    let input = ProblemInput { filter, ..input.clone() };
+    Bom::optimized(&input, full_book)
+}
+
+/// The largest scale of the targets with the given filter, snapped to its exact value
+fn maximal_scale(input: &ProblemInput, full_book: &FullBook, filter: RecipeFilter) -> Result<f64> {
+    let book = full_book.filter(&filter)?;
+    let input = ProblemInput { filter, ..input.clone() };
+    let scale = Factory::create_problem(&input, &book)?.maximal_scale()?;
+    Ok(Rate::snapped(scale).as_f64())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::alternate_ranking::only_alternate;
+    use crate::model::full_book::FullBook;
+
+    #[test]
+    fn unlock_a_single_alternate() {
+        let book = FullBook::create().unwrap();
+        let recipe = |id: &str| book.recipes().iter().find(|r| r.id() == id).unwrap();
+        let filter = only_alternate(recipe("casted_screw"));
+
+        assert!(filter.matches(recipe("casted_screw")));
+        assert!(filter.matches(recipe("screw")));
+        assert!(!filter.matches(recipe("steel_screw")));
+    }
+}
@@ -4,10 +4,13 @@ use std::hash::{Hash, Hasher};
 use dot::{Edges, GraphWalk, Id, Labeller, LabelText, Nodes, Style};
 use dot::LabelText::LabelStr;
 use dot::Style::{Filled};
+use crate::model::clock_speed::ClockSpeeds;
 use crate::model::item::Item;
+use crate::model::name_format::NameFormat;
+use crate::model::rate::Rate;
+use crate::problem_input::ByproductPolicy;
 use crate::{AmountFormat, Bom, Recipe};
-use crate::constants::{is_nil, is_not_nil};
-use crate::bom_graph::ItemType::{Available, Intermediate, LeftOver, Requirement, Target};
+use crate::bom_graph::ItemType::{Available, Intermediate, LeftOver, Requirement, Sunk, Target};
 
 
 #[derive(Clone, Eq, PartialEq, Hash, Debug)]
@@ -17,13 +20,15 @@ pub enum ItemType {
     Target,
     Requirement,
     LeftOver,
+    //a leftover sent to an AWESOME Sink
+    Sunk,
 }
 
 
 #[derive(Clone)]
 pub enum Node {
-    Item(Item, f64, ItemType),
-    Recipe(Recipe, f64),
+    Item(Item, Rate, ItemType),
+    Recipe(Recipe, Rate),
 }
 
 impl Eq for Node {}
@@ -59,11 +64,14 @@ pub struct Graph {
     nodes: Vec<Node>,
     edges: Vec<(usize, usize)>,
     amount_format: AmountFormat,
+    name_format: NameFormat,
+    clock_speeds: ClockSpeeds,
+    byproduct_policies: HashMap<Item, ByproductPolicy>,
 }
 
 impl Graph {
-    pub fn new(bom: &Bom, amount_format: AmountFormat) -> Self {
-        let mut factory = GraphFactory::new(bom, amount_format);
+    pub fn new(bom: &Bom, amount_format: AmountFormat, name_format: NameFormat) -> Self {
+        let mut factory = GraphFactory::new(bom, amount_format, name_format);
         factory.build();
         factory.into()
     }
@@ -77,17 +85,25 @@ struct GraphFactory<'a> {
     nodes: Vec<Node>,
     edges: HashSet<(usize, usize)>,
     amount_format: AmountFormat,
+    name_format: NameFormat,
 }
 
 impl From<GraphFactory<'_>> for Graph {
     fn from(factory: GraphFactory<'_>) -> Self {
-        Graph { nodes: factory.nodes, edges: factory.edges.into_iter().collect(), amount_format: factory.amount_format }
+        Graph {
+            nodes: factory.nodes,
+            edges: factory.edges.into_iter().collect(),
+            amount_format: factory.amount_format,
+            name_format: factory.name_format,
+            clock_speeds: factory.bom.clock_speeds.clone(),
+            byproduct_policies: factory.bom.byproduct_policies.clone(),
+        }
     }
 }
 
 impl<'a> GraphFactory<'a> {
-    fn new(bom: &'a Bom, amount_format: AmountFormat) -> Self {
-        GraphFactory { bom, nodes: vec![], node_index: HashMap::new(), edges: HashSet::new(), amount_format }
+    fn new(bom: &'a Bom, amount_format: AmountFormat, name_format: NameFormat) -> Self {
+        GraphFactory { bom, nodes: vec![], node_index: HashMap::new(), edges: HashSet::new(), amount_format, name_format }
     }
 }
 
@@ -98,15 +114,18 @@ impl<'a> GraphFactory<'a> {
         let items = self.bom.get_all_items();
 
         for (item, (using, producing)) in items {
-            let available_amount = self.bom.get_available_amount(item).cloned().unwrap_or(0f64);
-            let target_amount = self.bom.get_targeted_amount(item).cloned().unwrap_or(0f64);
-            let leftover_amount = self.bom.get_leftover_amount(item).cloned().unwrap_or(0f64);
+            let available_amount = self.bom.get_available_amount(item).cloned().unwrap_or_default();
+            let target_amount = self.bom.get_targeted_amount(item).cloned().unwrap_or_default();
+            let (leftover_amount, leftover_type) = match self.bom.get_sunk_amount(item) {
+                Some(sunk) => (*sunk, Sunk),
+                None => (self.bom.get_leftover_amount(item).cloned().unwrap_or_default(), LeftOver),
+            };
 
-            let produced_amount = producing.iter().map(|u| u.quantity).sum();
-            let used_amount = using.iter().map(|u| u.quantity).sum();
+            let produced_amount: Rate = producing.iter().map(|u| u.quantity).sum();
+            let used_amount: Rate = using.iter().map(|u| u.quantity).sum();
 
-            let is_produced = is_not_nil(produced_amount);
-            let is_used = is_not_nil(used_amount);
+            let is_produced = !produced_amount.is_zero();
+            let is_used = !used_amount.is_zero();
 
 
             match (is_used, is_produced) {
@@ -121,34 +140,34 @@ impl<'a> GraphFactory<'a> {
                         self.edges.insert((recipe_index,node_index));
                     }
 
-                    if is_not_nil(target_amount) {
+                    if !target_amount.is_zero() {
                         let target_node_index = self.add_item_node(item, target_amount, ItemType::Target);
                         self.edges.insert((node_index, target_node_index));
                     }
-                    if is_not_nil(available_amount) {
+                    if !available_amount.is_zero() {
                         let available_node_index = self.add_item_node(item, available_amount, ItemType::Available);
                         self.edges.insert((available_node_index, node_index));
                     }
 
-                    if is_not_nil(leftover_amount) {
-                        let leftover_node_index = self.add_item_node(item, leftover_amount, ItemType::LeftOver);
+                    if !leftover_amount.is_zero() {
+                        let leftover_node_index = self.add_item_node(item, leftover_amount, leftover_type.clone());
                         self.edges.insert((node_index, leftover_node_index));
                     }
 
                     let missing = used_amount - (available_amount+produced_amount);
 
-                    if missing>0f64 && is_not_nil(missing)  {
+                    if missing.is_positive() {
                         let requirement_node_index = self.add_item_node(item,missing,Requirement);
                         self.edges.insert((requirement_node_index,node_index));
                     }
 
                 }
                 (true, false) => {
-                    let node_index = if is_nil(used_amount - available_amount) {
+                    let node_index = if (used_amount - available_amount).is_zero() {
                         self.add_item_node(item, available_amount, Available)
                     } else {
                         let node_index = self.add_item_node(item, used_amount, Requirement);
-                        if is_not_nil(available_amount) {
+                        if !available_amount.is_zero() {
                             let available_index = self.add_item_node(item, available_amount, Available);
                             self.edges.insert((available_index, node_index));
                         }
@@ -161,7 +180,7 @@ impl<'a> GraphFactory<'a> {
                     }
                 }
                 (false, true) => {
-                    let node_index = match (is_not_nil(target_amount), is_not_nil(leftover_amount)) {
+                    let node_index = match (!target_amount.is_zero(), !leftover_amount.is_zero()) {
                         (false, false) => None,
                         (true, false) => {
                             let node_index = self.add_item_node(item, target_amount, Target);
@@ -170,13 +189,13 @@ impl<'a> GraphFactory<'a> {
                         (true, true) => {
                             let node_index = self.add_item_node(item, produced_amount, ItemType::Intermediate);
                             let target_idx = self.add_item_node(item, target_amount, Target);
-                            let leftover_idx = self.add_item_node(item, leftover_amount, LeftOver);
+                            let leftover_idx = self.add_item_node(item, leftover_amount, leftover_type.clone());
                             self.edges.insert((node_index, target_idx));
                             self.edges.insert((node_index, leftover_idx));
                             Some(node_index)
                         }
                         (false, true) => {
-                            let node_index = self.add_item_node(item, leftover_amount, LeftOver);
+                            let node_index = self.add_item_node(item, leftover_amount, leftover_type.clone());
                             Some(node_index)
                         }
                     };
@@ -186,14 +205,14 @@ impl<'a> GraphFactory<'a> {
                             let recipe_index = self.get_recipe_node_index(iu.recipe);
                             self.edges.insert((recipe_index, node_index));
                         }
-                        if is_not_nil(available_amount) {
+                        if !available_amount.is_zero() {
                             let available_node_index = self.add_item_node(item, available_amount, ItemType::Available);
                             self.edges.insert((node_index, available_node_index));
                         }
                     }
                 }
                 (false, false) => {
-                    let node_index = match (is_not_nil(target_amount), is_not_nil(leftover_amount)) {
+                    let node_index = match (!target_amount.is_zero(), !leftover_amount.is_zero()) {
                         (false, false) => None,
                         (true, false) => {
                             let node_index = self.add_item_node(item, target_amount, Target);
@@ -202,13 +221,13 @@ impl<'a> GraphFactory<'a> {
                         (true, true) => {
                             let node_index = self.add_item_node(item, target_amount + leftover_amount, ItemType::Intermediate);
                             let target_idx = self.add_item_node(item, target_amount, Target);
-                            let leftover_idx = self.add_item_node(item, leftover_amount, LeftOver);
+                            let leftover_idx = self.add_item_node(item, leftover_amount, leftover_type.clone());
                             self.edges.insert((node_index, target_idx));
                             self.edges.insert((node_index, leftover_idx));
                             Some(node_index)
                         }
                         (false, true) => {
-                            let node_index = self.add_item_node(item, leftover_amount, LeftOver);
+                            let node_index = self.add_item_node(item, leftover_amount, leftover_type.clone());
                             Some(node_index)
                         }
                     };
@@ -218,7 +237,7 @@ impl<'a> GraphFactory<'a> {
                             let recipe_index = self.get_recipe_node_index(iu.recipe);
                             self.edges.insert((recipe_index, node_index));
                         }
-                        if is_not_nil(available_amount) {
+                        if !available_amount.is_zero() {
                             let available_node_index = self.add_item_node(item, available_amount, ItemType::Available);
                             self.edges.insert((node_index, available_node_index));
                         }
@@ -249,18 +268,18 @@ impl GraphFactory<'_> {
         }
     }
 
-    fn add_item_node(&mut self, item: &Item, amount: f64, item_type: ItemType) -> usize {
+    fn add_item_node(&mut self, item: &Item, amount: Rate, item_type: ItemType) -> usize {
         let node = Node::Item(item.clone(), amount, item_type);
         self.add_node(node)
     }
 
-    fn add_recipe_node(&mut self, recipe: &Recipe, amount: f64) -> usize {
+    fn add_recipe_node(&mut self, recipe: &Recipe, amount: Rate) -> usize {
         let node = Node::Recipe(recipe.clone(), amount);
         self.add_node(node)
     }
 
     fn get_recipe_node_index(&self, recipe: &Recipe) -> usize {
-        let node = Node::Recipe(recipe.clone(), 0f64);
+        let node = Node::Recipe(recipe.clone(), Rate::default());
         *(self.node_index.get(&node).unwrap())
     }
 
@@ -302,8 +321,11 @@ impl<'a> Labeller<'a, Nd<'a>, Ed> for Graph {
         Id::new(id).unwrap()
     }
 
-    fn node_shape(&'a self, _node: &Nd<'a>) -> Option<LabelText<'a>> {
-        Some(LabelStr(Cow::Borrowed("box")))
+    fn node_shape(&'a self, node: &Nd<'a>) -> Option<LabelText<'a>> {
+        match node {
+            Node::Item(item, _, _) if item.is_fluid() => Some(LabelStr(Cow::Borrowed("ellipse"))),
+            _ => Some(LabelStr(Cow::Borrowed("box")))
+        }
     }
 
 
@@ -324,6 +346,7 @@ impl<'a> Labeller<'a, Nd<'a>, Ed> for Graph {
             Node::Item(_, _, Available) => "#FFD512",
             Node::Item(_, _, Intermediate) => "#000000",
             Node::Item(_, _, LeftOver) => "#DC14FF",
+            Node::Item(_, _, Sunk) => "#FF9C1A",
         };
 
         Some(LabelText::LabelStr(Cow::Borrowed(color)))
@@ -331,17 +354,33 @@ impl<'a> Labeller<'a, Nd<'a>, Ed> for Graph {
 
 
     fn node_label(&'a self, n: &Nd<'a>) -> LabelText<'a> {
-        let (name, a) = match n {
-            Node::Recipe(r, a) => (r.id().replace("_", " "), a / r.nb_per_minute()),
-            Node::Item(t, a, _) => (t.id().replace("_", " "), *a)
+        let (name, amount, unit) = match n {
+            Node::Recipe(r, a) => (self.name_format.recipe(r).replace('_', " "), self.amount_format.format(&self.clock_speeds.building_count(r, a.as_f64())), ""),
+            Node::Item(t, a, _) if t.is_fluid() => (self.name_format.item(t).replace('_', " "), self.amount_format.format_rate(a), " m³/min"),
+            Node::Item(t, a, _) => (self.name_format.item(t).replace('_', " "), self.amount_format.format_rate(a), "")
         };
 
-        let label = format!("{}\n{}", name, self.amount_format.format(&a));
+        let label = match n {
+            Node::Item(t, a, Sunk) => format!("{}\n{}{}\n{} points/min", name, amount, unit, self.amount_format.format_rate(&(*a * t.metadata().sink_points().unwrap_or(0)))),
+            Node::Item(t, _, LeftOver) => match self.byproduct_policies.get(t) {
+                Some(policy) => format!("{}\n{}{}\n{}", name, amount, unit, policy),
+                None => format!("{}\n{}{}", name, amount, unit),
+            },
+            _ => format!("{}\n{}{}", name, amount, unit),
+        };
 
 
         LabelText::LabelStr(Cow::Owned(label))
     }
 
+    /// Fluids go through pipes, their edges are dashed
+    fn edge_style(&'a self, e: &Ed) -> Style {
+        match (&self.nodes[e.0], &self.nodes[e.1]) {
+            (Node::Item(item, _, _), _) | (_, Node::Item(item, _, _)) if item.is_fluid() => Style::Dashed,
+            _ => Style::None
+        }
+    }
+
     fn edge_label(&'a self, e: &Ed) -> LabelText<'a> {
         let node0 = &self.nodes[e.0];
         let node1 = &self.nodes[e.1];
@@ -349,12 +388,11 @@ impl<'a> Labeller<'a, Nd<'a>, Ed> for Graph {
         match (node0, node1) {
             (Node::Item(item, item_amount, _), Node::Recipe(recipe, recipe_amount)) => {
                 if let Some(re) = recipe.input_reactant(item) {
-                    let consumed = re.quantity_f64() * recipe_amount;
-                    let available = item_amount;
-                    if is_nil(consumed - available) {
+                    let consumed = *recipe_amount * re.quantity();
+                    if consumed == *item_amount {
                         LabelStr(Cow::Borrowed(""))
                     } else {
-                        LabelStr(Cow::Owned(format!("{:.2}", consumed)))
+                        LabelStr(Cow::Owned(format!("{:.2}", consumed.as_f64())))
                     }
                 } else {
                     LabelStr(Cow::Borrowed(""))
@@ -362,12 +400,11 @@ impl<'a> Labeller<'a, Nd<'a>, Ed> for Graph {
             }
             (Node::Recipe(recipe, recipe_amount), Node::Item(item, item_amount, _)) => {
                 if let Some(re) = recipe.output_reactant(item) {
-                    let produced = re.quantity_f64() * recipe_amount;
-                    let available = item_amount;
-                    if is_nil(produced - available) {
+                    let produced = *recipe_amount * re.quantity();
+                    if produced == *item_amount {
                         LabelStr(Cow::Borrowed(""))
                     } else {
-                        LabelStr(Cow::Owned(format!("{:.2}", produced)))
+                        LabelStr(Cow::Owned(format!("{:.2}", produced.as_f64())))
                     }
                 } else {
                     LabelStr(Cow::Borrowed(""))
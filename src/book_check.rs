@@ -0,0 +1,170 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+
+use crate::model::dto::book::BookDto;
+use crate::model::dto::item::ItemDto;
+
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum Subject {
+    Building,
+    Item,
+    Recipe,
+}
+
+#[derive(Debug)]
+pub struct Finding {
+    pub severity: Severity,
+    pub subject: Subject,
+    pub id: String,
+    pub message: String,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Error => f.write_str("error"),
+            Severity::Warning => f.write_str("warning"),
+        }
+    }
+}
+
+impl Display for Subject {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Subject::Building => f.write_str("building"),
+            Subject::Item => f.write_str("item"),
+            Subject::Recipe => f.write_str("recipe"),
+        }
+    }
+}
+
+/// Check the integrity of a book.
+///
+/// The checks are done on the raw book since a book with unknown
+/// or duplicate ids cannot be converted into a FullBook.
+pub fn check_book(book: &BookDto) -> Vec<Finding> {
+    let mut findings = vec![];
+
+    check_duplicates(Subject::Building, book.buildings.iter().map(|b| b.id()), &mut findings);
+    check_duplicates(Subject::Item, book.items.iter().map(|i| i.id()), &mut findings);
+    check_duplicates(Subject::Recipe, book.recipes.iter().map(|r| r.id.as_str()), &mut findings);
+
+    let building_ids: HashSet<&str> = book.buildings.iter().map(|b| b.id()).collect();
+    let item_ids: HashSet<&str> = book.items.iter().map(|i| i.id()).collect();
+    let mut consumed = HashSet::new();
+    let mut produced = HashSet::new();
+
+    for recipe in &book.recipes {
+        if !building_ids.contains(recipe.building.as_str()) {
+            findings.push(error(Subject::Recipe, &recipe.id, format!("uses the unknown building '{}'", recipe.building)));
+        }
+        if recipe.duration == 0 {
+            findings.push(error(Subject::Recipe, &recipe.id, "has a duration of zero".to_string()));
+        }
+        for reactant in recipe.inputs.iter().chain(recipe.outputs.iter()) {
+            if !item_ids.contains(reactant.item_id.as_str()) {
+                findings.push(error(Subject::Recipe, &recipe.id, format!("refers to the unknown item '{}'", reactant.item_id)));
+            }
+        }
+        consumed.extend(recipe.inputs.iter().map(|r| r.item_id.as_str()));
+        produced.extend(recipe.outputs.iter().map(|r| r.item_id.as_str()));
+    }
+
+    for item in &book.items {
+        let id = item.id();
+        match item {
+            ItemDto::Resource(r) => {
+                let unspecified = r.impure < 0 && r.normal < 0 && r.pure < 0;
+                let total = r.impure.max(0) + r.normal.max(0) + r.pure.max(0);
+                if r.extractor != "manual" && !unspecified && total == 0 {
+                    findings.push(error(Subject::Item, id, "is a resource without any node".to_string()));
+                }
+            }
+            ItemDto::Product(_) => {
+                if !produced.contains(id) {
+                    findings.push(warning(Subject::Item, id, "is not produced by any recipe".to_string()));
+                }
+            }
+        }
+        if !consumed.contains(id) {
+            findings.push(warning(Subject::Item, id, "is never consumed by any recipe".to_string()));
+        }
+    }
+
+    findings.sort_by_key(|f| f.severity);
+    findings
+}
+
+fn check_duplicates<'a>(subject: Subject, ids: impl Iterator<Item=&'a str>, findings: &mut Vec<Finding>) {
+    let mut counts: HashMap<&str, u32> = HashMap::new();
+    for id in ids {
+        *counts.entry(id).or_default() += 1;
+    }
+
+    let mut duplicates: Vec<(&str, u32)> = counts.into_iter().filter(|(_, c)| *c > 1).collect();
+    duplicates.sort_unstable();
+    for (id, count) in duplicates {
+        findings.push(error(subject, id, format!("is defined {} times", count)));
+    }
+}
+
+fn error(subject: Subject, id: &str, message: String) -> Finding {
+    Finding { severity: Severity::Error, subject, id: id.to_string(), message }
+}
+
+fn warning(subject: Subject, id: &str, message: String) -> Finding {
+    Finding { severity: Severity::Warning, subject, id: id.to_string(), message }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::book_check::{check_book, Finding, Severity, Subject};
+    use crate::model::dto::book::BookDto;
+
+    fn has_finding(findings: &[Finding], severity: Severity, subject: Subject, id: &str) -> bool {
+        findings.iter().any(|f| f.severity == severity && f.subject == subject && f.id == id)
+    }
+
+    #[test]
+    fn embedded_book_has_no_error() {
+        let findings = check_book(&BookDto::parse().unwrap());
+        assert!(findings.iter().all(|f| f.severity == Severity::Warning));
+    }
+
+    #[test]
+    fn report_broken_entries() {
+        let book: BookDto = serde_json::from_str(r#"{
+            "name": "broken",
+            "buildings": [{"type": "builder", "id": "constructor", "power-usage": 4}],
+            "items": [
+                {"id": "iron_ore", "extractor": "miner", "impure": 0, "normal": 0, "pure": 0},
+                {"id": "iron_plate"},
+                {"id": "iron_rod"},
+                {"id": "iron_rod"}
+            ],
+            "recipes": [
+                {"id": "iron_plate", "building": "constructor", "alternate": false, "duration": 0,
+                 "inputs": [{"item": "iron_ingot", "quantity": 3}], "outputs": [{"item": "iron_plate", "quantity": 2}]},
+                {"id": "iron_rod", "building": "smelter", "alternate": false, "duration": 4,
+                 "inputs": [{"item": "iron_plate", "quantity": 1}], "outputs": [{"item": "iron_rod", "quantity": 1}]}
+            ]
+        }"#).unwrap();
+
+        let findings = check_book(&book);
+
+        assert!(has_finding(&findings, Severity::Error, Subject::Recipe, "iron_plate"));
+        assert!(has_finding(&findings, Severity::Error, Subject::Recipe, "iron_rod"));
+        assert!(has_finding(&findings, Severity::Error, Subject::Item, "iron_ore"));
+        assert!(has_finding(&findings, Severity::Error, Subject::Item, "iron_rod"));
+        assert!(has_finding(&findings, Severity::Warning, Subject::Item, "iron_rod"));
+        assert!(!has_finding(&findings, Severity::Warning, Subject::Item, "iron_plate"));
+        assert_eq!(findings.iter().filter(|f| f.severity == Severity::Error).count(), 5);
+    }
+}
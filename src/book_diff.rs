@@ -0,0 +1,146 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::Serialize;
+
+use crate::model::dto::book::BookDto;
+use crate::model::dto::reactant::ReactantDto;
+use crate::model::dto::recipe::RecipeDto;
+
+/// The differences between two books
+#[derive(Serialize, Debug, Default)]
+pub struct BookDiff {
+    #[serde(rename = "added-items")]
+    pub added_items: Vec<String>,
+    #[serde(rename = "removed-items")]
+    pub removed_items: Vec<String>,
+    #[serde(rename = "added-buildings")]
+    pub added_buildings: Vec<String>,
+    #[serde(rename = "removed-buildings")]
+    pub removed_buildings: Vec<String>,
+    #[serde(rename = "added-recipes")]
+    pub added_recipes: Vec<String>,
+    #[serde(rename = "removed-recipes")]
+    pub removed_recipes: Vec<String>,
+    #[serde(rename = "changed-recipes")]
+    pub changed_recipes: Vec<RecipeChanges>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct RecipeChanges {
+    pub id: String,
+    pub changes: Vec<RecipeChange>,
+}
+
+#[derive(Serialize, Debug, Eq, PartialEq)]
+#[serde(tag = "field")]
+pub enum RecipeChange {
+    #[serde(rename = "duration")]
+    Duration { old: u32, new: u32 },
+    #[serde(rename = "building")]
+    Building { old: String, new: String },
+    #[serde(rename = "alternate")]
+    Alternate { old: bool, new: bool },
+    #[serde(rename = "input")]
+    Input { item: String, old: Option<u32>, new: Option<u32> },
+    #[serde(rename = "output")]
+    Output { item: String, old: Option<u32>, new: Option<u32> },
+}
+
+impl BookDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added_items.is_empty() && self.removed_items.is_empty()
+            && self.added_buildings.is_empty() && self.removed_buildings.is_empty()
+            && self.added_recipes.is_empty() && self.removed_recipes.is_empty()
+            && self.changed_recipes.is_empty()
+    }
+}
+
+/// Compute the differences to go from the book `old` to the book `new`
+pub fn diff_books(old: &BookDto, new: &BookDto) -> BookDiff {
+    let (added_items, removed_items) = diff_ids(old.items.iter().map(|i| i.id()), new.items.iter().map(|i| i.id()));
+    let (added_buildings, removed_buildings) = diff_ids(old.buildings.iter().map(|b| b.id()), new.buildings.iter().map(|b| b.id()));
+    let (added_recipes, removed_recipes) = diff_ids(old.recipes.iter().map(|r| r.id.as_str()), new.recipes.iter().map(|r| r.id.as_str()));
+
+    let new_recipes: BTreeMap<&str, &RecipeDto> = new.recipes.iter().map(|r| (r.id.as_str(), r)).collect();
+    let mut changed_recipes: Vec<RecipeChanges> = old.recipes.iter()
+        .filter_map(|old_recipe| new_recipes.get(old_recipe.id.as_str()).map(|new_recipe| (old_recipe, new_recipe)))
+        .map(|(old_recipe, new_recipe)| RecipeChanges { id: old_recipe.id.clone(), changes: diff_recipes(old_recipe, new_recipe) })
+        .filter(|c| !c.changes.is_empty())
+        .collect();
+    changed_recipes.sort_by(|c1, c2| c1.id.cmp(&c2.id));
+
+    BookDiff { added_items, removed_items, added_buildings, removed_buildings, added_recipes, removed_recipes, changed_recipes }
+}
+
+fn diff_ids<'a>(old: impl Iterator<Item=&'a str>, new: impl Iterator<Item=&'a str>) -> (Vec<String>, Vec<String>) {
+    let old: BTreeSet<&str> = old.collect();
+    let new: BTreeSet<&str> = new.collect();
+
+    let added = new.difference(&old).map(|i| i.to_string()).collect();
+    let removed = old.difference(&new).map(|i| i.to_string()).collect();
+    (added, removed)
+}
+
+fn diff_recipes(old: &RecipeDto, new: &RecipeDto) -> Vec<RecipeChange> {
+    let mut changes = vec![];
+    if old.duration != new.duration {
+        changes.push(RecipeChange::Duration { old: old.duration, new: new.duration });
+    }
+    if old.building != new.building {
+        changes.push(RecipeChange::Building { old: old.building.clone(), new: new.building.clone() });
+    }
+    if old.alternate != new.alternate {
+        changes.push(RecipeChange::Alternate { old: old.alternate, new: new.alternate });
+    }
+    for (item, old, new) in diff_reactants(&old.inputs, &new.inputs) {
+        changes.push(RecipeChange::Input { item, old, new });
+    }
+    for (item, old, new) in diff_reactants(&old.outputs, &new.outputs) {
+        changes.push(RecipeChange::Output { item, old, new });
+    }
+    changes
+}
+
+fn diff_reactants(old: &[ReactantDto], new: &[ReactantDto]) -> Vec<(String, Option<u32>, Option<u32>)> {
+    let mut quantities: BTreeMap<&str, (Option<u32>, Option<u32>)> = BTreeMap::new();
+    for reactant in old {
+        quantities.entry(&reactant.item_id).or_default().0 = Some(reactant.quantity);
+    }
+    for reactant in new {
+        quantities.entry(&reactant.item_id).or_default().1 = Some(reactant.quantity);
+    }
+
+    quantities.into_iter()
+        .filter(|(_, (old, new))| old != new)
+        .map(|(item, (old, new))| (item.to_string(), old, new))
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::book_diff::{diff_books, RecipeChange};
+    use crate::model::dto::book::BookDto;
+
+    #[test]
+    fn diff_modified_book() {
+        let old = BookDto::parse().unwrap();
+        let mut new = BookDto::parse().unwrap();
+        new.recipes.retain(|r| r.id != "screw");
+        let iron_plate = new.recipes.iter_mut().find(|r| r.id == "iron_plate").unwrap();
+        iron_plate.duration = 4;
+        iron_plate.inputs[0].quantity = 2;
+
+        let diff = diff_books(&old, &new);
+
+        assert_eq!(diff.removed_recipes, vec!["screw"]);
+        assert!(diff.added_recipes.is_empty());
+        assert!(diff.added_items.is_empty() && diff.removed_items.is_empty());
+        assert_eq!(diff.changed_recipes.len(), 1);
+        assert_eq!(diff.changed_recipes[0].changes, vec![
+            RecipeChange::Duration { old: 6, new: 4 },
+            RecipeChange::Input { item: "iron_ingot".to_string(), old: Some(3), new: Some(2) },
+        ]);
+        assert!(diff_books(&old, &old).is_empty());
+    }
+}
@@ -5,3 +5,9 @@ pub const DURATION_COLOR:Color = term::color::WHITE;
 pub const CONSTRUCTOR_COLOR:Color = term::color::BRIGHT_RED;
 pub const ITEM_COLOR:Color = term::color::BRIGHT_GREEN;
 pub const AMOUNT_COLOR:Color = term::color::WHITE;
+pub const ERROR_COLOR:Color = term::color::BRIGHT_RED;
+pub const WARNING_COLOR:Color = term::color::BRIGHT_YELLOW;
+pub const OVERLAY_COLOR:Color = term::color::BRIGHT_MAGENTA;
+pub const ADDED_COLOR:Color = term::color::BRIGHT_GREEN;
+pub const REMOVED_COLOR:Color = term::color::BRIGHT_RED;
+pub const CHANGED_COLOR:Color = term::color::BRIGHT_YELLOW;
@@ -0,0 +1,160 @@
+use std::collections::{BTreeSet, HashSet};
+use std::fmt::{Display, Formatter};
+
+use crate::error::Result;
+use crate::factory::Factory;
+use crate::model::book::{Book, FilterableBook};
+use crate::model::filtered_book::FilteredBook;
+use crate::model::full_book::FullBook;
+use crate::model::item::Item;
+use crate::problem::Relaxation;
+use crate::problem_input::ProblemInput;
+use crate::recipe_filter::RecipeFilter;
+
+/// The reasons why a problem has no solution
+#[derive(Debug)]
+pub struct Diagnosis {
+    culprits: Vec<Culprit>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Culprit {
+    //the target can be produced with all the recipes of the book, but not with those kept by the filters
+    Filtered { item: String, filters: Vec<String> },
+    //no recipe of the book produces the target from the resources and the available items
+    Unproducible(String),
+    //the problem has a solution without this constraint
+    Binding(Relaxation),
+}
+
+/// Look for the targets that can not be reached with the filtered recipes first,
+/// then for the constraints that make the problem feasible once removed
+pub fn diagnose(input: &ProblemInput, full_book: &FullBook, book: &FilteredBook) -> Result<Diagnosis> {
+    let available_items = input.available_items().keys()
+        .map(|id| book.get_item_by_id(id).cloned())
+        .collect::<Result<HashSet<Item>>>()?;
+    let targets = input.target_items().keys()
+        .map(|id| book.get_item_by_id(id).cloned())
+        .collect::<Result<Vec<Item>>>()?;
+
+    let reachable = reachable_items(book, &available_items)?;
+    let unreachable: Vec<&Item> = targets.iter().filter(|t| !reachable.contains(t)).collect();
+
+    if !unreachable.is_empty() {
+        let all_recipes = full_book.filter(&RecipeFilter::AllRecipes)?;
+        let reachable_without_filter = reachable_items(&all_recipes, &available_items)?;
+        let filters = excluding_filters(full_book, input.filter(), &reachable, &reachable_without_filter);
+
+        let culprits = unreachable.into_iter()
+            .map(|item| if reachable_without_filter.contains(item) {
+                Culprit::Filtered { item: item.id().to_string(), filters: filters.clone() }
+            } else {
+                Culprit::Unproducible(item.id().to_string())
+            })
+            .collect();
+        return Ok(Diagnosis { culprits });
+    }
+
+    let mut culprits = vec![];
+    for relaxation in Factory::create_problem(input, book)?.relaxations() {
+        let relaxed = Factory::create_problem(input, book)?.relaxed(relaxation.clone());
+        if relaxed.solvable()? {
+            culprits.push(Culprit::Binding(relaxation));
+        }
+    }
+    Ok(Diagnosis { culprits })
+}
+
+/// The items that can be made from the resources and the available items with the recipes of the book
+fn reachable_items(book: &dyn Book, available_items: &HashSet<Item>) -> Result<HashSet<Item>> {
+    let recipes = (0..book.number_of_recipes())
+        .map(|i| book.get_recipe(i))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut reachable: HashSet<Item> = available_items.clone();
+    reachable.extend(book.get_involved_items()?.into_iter().filter(|i| i.as_resource().is_some()));
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for recipe in &recipes {
+            if recipe.inputs().iter().all(|r| reachable.contains(r.item())) {
+                for output in recipe.outputs() {
+                    changed |= reachable.insert(output.item().clone());
+                }
+            }
+        }
+    }
+    Ok(reachable)
+}
+
+/// The filters rejecting a recipe that could make an item unreachable with the filtered recipes
+fn excluding_filters(full_book: &FullBook, filter: &RecipeFilter, reachable: &HashSet<Item>, reachable_without_filter: &HashSet<Item>) -> Vec<String> {
+    let filters: BTreeSet<String> = full_book.recipes().iter()
+        .filter(|r| r.inputs().iter().all(|i| reachable_without_filter.contains(i.item())))
+        .filter(|r| r.outputs().iter().any(|o| !reachable.contains(o.item())))
+        .flat_map(|r| filter.rejecting(r))
+        .map(|f| f.to_string())
+        .collect();
+    filters.into_iter().collect()
+}
+
+impl Display for Diagnosis {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "The problem has no solution")?;
+        if self.culprits.is_empty() {
+            return write!(f, "  no single constraint explains it, several of them conflict");
+        }
+        for (i, culprit) in self.culprits.iter().enumerate() {
+            if i != 0 {
+                writeln!(f)?;
+            }
+            write!(f, "  - {}", culprit)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Culprit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Culprit::Filtered { item, filters } => write!(f, "'{}' can not be produced with the recipes kept by the filters {}", item, filters.join(", ")),
+            Culprit::Unproducible(item) => write!(f, "'{}' can not be produced from the resources and the available items", item),
+            Culprit::Binding(Relaxation::ResourceCap(item)) => write!(f, "the extraction limit of '{}' is too low", item.id()),
+            Culprit::Binding(Relaxation::AvailableItem(item)) => write!(f, "the available amount of '{}' is too low", item.id()),
+            Culprit::Binding(Relaxation::TargetMax(item)) => write!(f, "the amount of '{}' must exceed its maximum, it is also a byproduct", item.id()),
+            Culprit::Binding(Relaxation::Byproduct(item)) => write!(f, "the leftover of '{}' is forbidden but the plan can not consume all of it", item.id()),
+            Culprit::Binding(Relaxation::Power) => write!(f, "the power setting can not be met"),
+            Culprit::Binding(Relaxation::MaxPower) => write!(f, "the max power is too low"),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::diagnosis::{Culprit, diagnose};
+    use crate::model::book::FilterableBook;
+    use crate::model::full_book::FullBook;
+    use crate::model::rate::Rate;
+    use crate::problem_input::{ProblemInput, TargetAmount};
+    use crate::recipe_filter::RecipeFilter::{AllOf, NotAlternate, NotNamed};
+
+    #[test]
+    fn filtered_target() {
+        let full_book = FullBook::create().unwrap();
+        let input = ProblemInput {
+            target_items: maplit::hashmap! {"iron_plate".to_string() => TargetAmount::Exact(Rate::per_minute(20))},
+            filter: AllOf(vec![NotAlternate, NotNamed("iron_plate".to_string())]),
+            ..Default::default()
+        };
+        let book = full_book.filter(&input.filter).unwrap();
+
+        let diagnosis = diagnose(&input, &full_book, &book).unwrap();
+
+        assert_eq!(diagnosis.culprits, vec![Culprit::Filtered {
+            item: "iron_plate".to_string(),
+            filters: vec!["not-alternate".to_string(), "wo_iron_plate".to_string()],
+        }]);
+    }
+}
@@ -3,22 +3,42 @@ use std::str::Utf8Error;
 use clap::ErrorKind;
 use good_lp::ResolutionError;
 use serde::de::StdError;
-use crate::error::Error::{BookDeserialization, Fmt, Io, Term};
+use crate::diagnosis::Diagnosis;
+use crate::error::Error::{BookDeserialization, Fmt, Io, Json, Term};
 
 #[derive(Debug)]
 pub enum Error {
     Io(std::io::Error),
     DotFailed,
     TargetParsingFailed(String),
+    RateParsingFailed(String),
     Fmt(std::fmt::Error),
     Term(term::Error),
     FilterParsingFailed(String),
+    PowerParsingFailed(String),
+    ObjectiveParsingFailed(String),
+    InvalidClockSpeed(f64),
+    ClockSpeedParsingFailed(String),
+    ResourceSettingParsingFailed(String),
+    ByproductPolicyParsingFailed(String),
+    NotAResource(String),
     UnknownItem(String),
+    AmbiguousItem(String, Vec<String>),
+    UnknownRecipe(String),
     UnknownBuilding(String),
     InvalidBuilding(String),
     InvalidRecipeIndex(usize),
     ResolutionFailed(ResolutionError),
-    BookDeserialization(serde_json::Error),
+    NotMaximizing,
+    UnbalancedSolution(String),
+    RateOverflow(String),
+    Infeasible(Diagnosis),
+    BookDeserialization(String, serde_json::Error),
+    Json(serde_json::Error),
+    XmlBookDeserialization(String, String),
+    DocsImportFailed(String),
+    UnknownBook(String),
+    InvalidBook(usize),
     Clap(ErrorKind),
     Utf8(Utf8Error),
 }
@@ -31,15 +51,34 @@ impl Display for Error {
             Error::DotFailed => "Could not execute dot executable".to_string(),
             Io(e) => format!("I/O error : {}", e),
             Error::TargetParsingFailed(e) => format!("fail to parse target '{}'",e),
+            Error::RateParsingFailed(e) => format!("fail to parse rate '{}', expected a number or a fraction with an optional unit like '7.5', '15/2' or '2/s'",e),
             Fmt(e) => e.to_string(),
             Term(e) => e.to_string(),
             Error::FilterParsingFailed(e) => e.clone(),
+            Error::PowerParsingFailed(e) => format!("fail to parse power setting '{}', expected 'external', 'self-powered' or a number of MW",e),
+            Error::ObjectiveParsingFailed(e) => format!("fail to parse objective '{}', expected criteria like 'resources+0.5*power' among resources, buildings, power, waste, recipes and shards",e),
+            Error::InvalidClockSpeed(c) => format!("Invalid clock speed '{}', it must be between 1% and 250%",c),
+            Error::ClockSpeedParsingFailed(e) => format!("fail to parse clock speed '{}', expected 'recipe=percent'",e),
+            Error::ResourceSettingParsingFailed(e) => format!("fail to parse resource setting '{}', expected 'resource=value'",e),
+            Error::ByproductPolicyParsingFailed(e) => format!("fail to parse byproduct policy '{}', expected 'allow', 'forbid', 'penalize', 'penalize:cost' or 'sink'",e),
+            Error::NotAResource(item) => format!("'{}' is not a resource, only resources can be capped or weighted",item),
             Error::UnknownItem(item) => format!("Unknown item '{}'",item),
+            Error::AmbiguousItem(name, ids) => format!("Ambiguous item name '{}', use one of the ids {}",name,ids.join(", ")),
+            Error::UnknownRecipe(recipe) => format!("Unknown recipe '{}'",recipe),
             Error::UnknownBuilding(building) => format!("Unknown building '{}'",building),
             Error::InvalidBuilding(building) => format!("Invalid building '{}'",building),
             Error::InvalidRecipeIndex(e) => format!("Invalid recipe index '{}'",e),
             Error::ResolutionFailed(e) => format!("Could not find a solution : {}", e),
-            BookDeserialization(e) => format!("Book deserialization failed : {}", e),
+            Error::Infeasible(diagnosis) => diagnosis.to_string(),
+            Error::NotMaximizing => "The problem does not maximize the production".to_string(),
+            Error::UnbalancedSolution(item) => format!("The exact amounts of the solution do not balance for '{}', the solver is not precise enough",item),
+            Error::RateOverflow(item) => format!("The exact amounts of the solution overflow for '{}', the solver is not precise enough",item),
+            BookDeserialization(source, e) => format!("Book deserialization failed in '{}' : {}", source, e),
+            Json(e) => format!("JSON error : {}", e),
+            Error::InvalidBook(nb_errors) => format!("The book contains {} error(s)", nb_errors),
+            Error::UnknownBook(book) => format!("Unknown book '{}'", book),
+            Error::DocsImportFailed(e) => format!("Docs.json import failed : {}", e),
+            Error::XmlBookDeserialization(source, e) => format!("Book deserialization failed in '{}' : {}", source, e),
             Error::Clap(e) => format!("{:?}", e),
             Error::Utf8(e) => format!("{}", e)
         };
@@ -86,6 +125,6 @@ impl From<ResolutionError> for Error {
 
 impl From<serde_json::Error> for Error {
     fn from(error: serde_json::Error) -> Self {
-        BookDeserialization(error)
+        Json(error)
     }
 }
\ No newline at end of file
@@ -3,7 +3,7 @@ use std::ops::{Mul, Neg};
 use good_lp::{Expression, ProblemVariables, Variable, variable};
 use hashlink::LinkedHashMap;
 use crate::model::book::Book;
-use crate::error::Result;
+use crate::error::{Error, Result};
 use crate::model::item::Item;
 use crate::model::recipe::Recipe;
 use crate::problem_input::ProblemInput;
@@ -42,8 +42,16 @@ impl<'b> Factory<'b> {
     fn create(self) -> Result<Problem> {
         let mut items = HashMap::new();
 
-        let target_items = convert_map(self.input.target_items(), self.book)?;
-        let available_items = convert_map(self.input.available_items(), self.book)?;
+        let target_items = convert_map(self.input.target_items(), self.book, |a| a.bounds())?;
+        let available_items = convert_map(self.input.available_items(), self.book, |q| q.as_f64())?;
+        let resource_caps = convert_resource_map(&self.input.resource_caps, self.book, |q| q.as_f64())?;
+        let resource_weights = convert_resource_map(&self.input.resource_weights, self.book, |w| *w)?;
+        let byproduct_policies = convert_map(&self.input.byproduct_policies, self.book, |p| *p)?;
+
+        //the targets that no recipe touches must still be constrained, to make the problem infeasible
+        for item in target_items.keys() {
+            add_item_quantity(&mut items, item, Expression::from(0))
+        }
 
         for (recipe, variable) in &(self.recipes) {
             for input in recipe.inputs() {
@@ -61,7 +69,12 @@ impl<'b> Factory<'b> {
         }
 
 
-        Ok(Problem::new(self.variables, target_items, available_items, self.recipes, items, self.input.use_abundances))
+        let clock_speeds = self.input.clock_speeds(self.book)?;
+
+        Ok(Problem::new(self.variables, target_items, available_items, self.recipes, items, clock_speeds, self.input)
+            .with_resource_caps(resource_caps)
+            .with_resource_weights(resource_weights)
+            .with_byproduct_policies(byproduct_policies))
     }
 }
 
@@ -80,12 +93,20 @@ fn remove_item_quantity(items: &mut HashMap<Item, Expression>, item: &Item, valu
 }
 
 
-fn convert_map(items: &HashMap<String, u32>, book: &dyn Book) -> Result<HashMap<Item, f64>> {
+fn convert_map<T, R>(items: &HashMap<String, T>, book: &dyn Book, convert: impl Fn(&T) -> R) -> Result<HashMap<Item, R>> {
     let mut result = HashMap::new();
     for (item_id, quantity) in items {
         let item = book.get_item_by_id(item_id)?.clone();
-        result.insert(item, *quantity as f64);
+        result.insert(item, convert(quantity));
     };
 
     Ok(result)
 }
+
+fn convert_resource_map<T, R>(items: &HashMap<String, T>, book: &dyn Book, convert: impl Fn(&T) -> R) -> Result<HashMap<Item, R>> {
+    let result = convert_map(items, book, convert)?;
+    match result.keys().find(|i| i.as_resource().is_none()) {
+        Some(item) => Err(Error::NotAResource(item.id().to_string())),
+        None => Ok(result)
+    }
+}
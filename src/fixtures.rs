@@ -0,0 +1,73 @@
+//! A small book, and the helpers shared by the tests that solve problems against it
+
+use std::collections::HashMap;
+
+use maplit::hashmap;
+
+use crate::error::Result;
+use crate::model::bom::Bom;
+use crate::model::dto::book::BookDto;
+use crate::model::full_book::FullBook;
+use crate::model::item::Item;
+use crate::model::rate::Rate;
+use crate::problem_input::ProblemInput;
+
+//iron plates made from ingots, or with one refinery recipe using less ore but producing slag,
+//and a coal generator burning coal and water
+const FIXTURE_BOOK: &str = r#"{
+    "name": "fixture",
+    "buildings": [
+        {"type": "miner", "id": "miner_mk1", "power-usage": 5, "normal-extraction-rate": 60},
+        {"type": "miner", "id": "water_extractor", "power-usage": 20, "normal-extraction-rate": 120},
+        {"type": "builder", "id": "smelter", "power-usage": 4},
+        {"type": "builder", "id": "constructor", "power-usage": 4},
+        {"type": "builder", "id": "refinery", "power-usage": 30},
+        {"type": "builder", "id": "coal_generator", "power-usage": -75}
+    ],
+    "items": [
+        {"id": "iron_ore", "extractor": "miner", "impure": 1, "normal": 1, "pure": 1},
+        {"id": "coal", "extractor": "miner", "impure": 0, "normal": 1, "pure": 0},
+        {"id": "water", "form": "fluid", "extractor": "water_extractor", "impure": 0, "normal": 0, "pure": 0},
+        {"id": "iron_ingot"},
+        {"id": "iron_plate"},
+        {"id": "slag", "sink-points": 5}
+    ],
+    "recipes": [
+        {"id": "iron_ingot", "building": "smelter", "alternate": false, "duration": 2,
+         "inputs": [{"item": "iron_ore", "quantity": 1}], "outputs": [{"item": "iron_ingot", "quantity": 1}]},
+        {"id": "iron_plate", "building": "constructor", "alternate": false, "duration": 6,
+         "inputs": [{"item": "iron_ingot", "quantity": 3}], "outputs": [{"item": "iron_plate", "quantity": 2}]},
+        {"id": "pure_iron_plate", "building": "refinery", "alternate": true, "duration": 12,
+         "inputs": [{"item": "iron_ore", "quantity": 2}], "outputs": [{"item": "iron_plate", "quantity": 3}, {"item": "slag", "quantity": 1}]},
+        {"id": "coal_power", "building": "coal_generator", "alternate": false, "duration": 4,
+         "inputs": [{"item": "coal", "quantity": 1}, {"item": "water", "quantity": 3}], "outputs": []}
+    ]
+}"#;
+
+pub fn fixture_book() -> FullBook {
+    let book: BookDto = serde_json::from_str(FIXTURE_BOOK).unwrap();
+    book.to_full_book().unwrap()
+}
+
+/// An input targeting some iron plates, like `20` or `10..15`
+pub fn plates(amount: &str) -> ProblemInput {
+    ProblemInput { target_items: hashmap! {"iron_plate".to_string() => amount.parse().unwrap()}, ..Default::default() }
+}
+
+pub fn solve(input: &ProblemInput) -> Result<Bom> {
+    Bom::optimized(input, &fixture_book())
+}
+
+/// The amounts of a section of a BoM as sorted `id: amount` lines, the amounts being exact fractions
+pub fn amounts(items: &HashMap<Item, Rate>) -> Vec<String> {
+    let mut lines: Vec<String> = items.iter().map(|(item, amount)| format!("{}: {}", item.id(), amount)).collect();
+    lines.sort();
+    lines
+}
+
+/// The executions per minute of the recipes of a BoM as sorted `id: amount` lines
+pub fn recipe_amounts(bom: &Bom) -> Vec<String> {
+    let mut lines: Vec<String> = bom.recipes.iter().map(|(recipe, amount)| format!("{}: {}", recipe.id(), amount)).collect();
+    lines.sort();
+    lines
+}
@@ -14,14 +14,23 @@ use bom_graph::Graph;
 use model::bom::Bom;
 use model::book::FilterableBook;
 
+use crate::book_check::Severity;
+use crate::book_diff::{BookDiff, RecipeChange};
+use crate::colors::{ADDED_COLOR, AMOUNT_COLOR, CHANGED_COLOR, ERROR_COLOR, OVERLAY_COLOR, RECIPE_NAME_COLOR, REMOVED_COLOR, WARNING_COLOR};
 use crate::error::{Error, Result};
 use crate::Error::Clap;
 use crate::model::amount_format::AmountFormat;
 use crate::model::bom_printer::BomPrinter;
+use crate::model::dto::book::BookDto;
+use crate::model::dto::docs_import;
+use crate::model::dto::registry::{DEFAULT_BOOK, EMBEDDED_BOOKS};
 use crate::model::full_book::FullBook;
+use crate::model::item::Item;
+use crate::model::name_format::NameFormat;
+use crate::model::rate::Rate;
 use crate::model::recipe::Recipe;
 use crate::model::recipe_complexity::sort_recipes;
-use crate::problem_input::ProblemInput;
+use crate::problem_input::{ByproductPolicy, Objective, PowerSetting, ProblemInput, TargetAmount};
 use crate::recipe_filter::RecipeFilter;
 use crate::recipe_filter::RecipeFilter::{AllOf, NotAlternate, NotManual, NotNamed};
 
@@ -34,6 +43,13 @@ mod colors;
 mod constants;
 mod recipe_filter;
 mod bom_graph;
+mod book_check;
+mod book_diff;
+mod diagnosis;
+mod sensitivity;
+mod alternate_ranking;
+#[cfg(test)]
+mod fixtures;
 
 #[derive(clap::Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
@@ -49,24 +65,174 @@ pub enum Command {
     /// Dump a template file that can be used by the bom subcommand
     Dump(DumpArg),
     /// Compute the BoM to produce some items
-    Bom(BomArg),
+    Bom(Box<BomArg>),
     Search(SearchArgs),
+    /// Generate a recipe book from the Docs.json file provided with the game
+    ImportDocs(ImportDocsArgs),
+    /// List the recipe books embedded in the executable
+    Books,
+    /// Check the integrity of a recipe book
+    CheckBook(CheckBookArgs),
+    /// List the differences between two recipe books
+    BookDiff(BookDiffArgs),
+    /// Rank the alternate recipes by what each of them saves when it is the only one unlocked
+    RankAlternates(RankAlternatesArgs),
+}
+
+#[derive(Parser, Debug)]
+pub struct BookArgs {
+    //Read the recipe book from the provided JSON or XML file instead of using the embedded one
+    #[clap(short, long, env = "SATISBOM_BOOK")]
+    book: Option<String>,
+
+    //Use the embedded book of the given game version (see the 'books' subcommand) instead of --book
+    #[clap(short = 'g', long, conflicts_with = "book")]
+    game_version: Option<String>,
+
+    //Apply the provided overlay file to the book. Can be repeated, overlays are applied in order
+    #[clap(long = "overlay", multiple_occurrences(true))]
+    overlays: Vec<String>,
+}
+
+impl BookArgs {
+    pub fn load(&self) -> Result<FullBook> {
+        self.load_dto()?.to_full_book()
+    }
+
+    pub fn load_dto(&self) -> Result<BookDto> {
+        let mut book = match (&self.game_version, &self.book) {
+            (Some(version), _) => BookDto::embedded(version),
+            (None, Some(path)) => BookDto::from_file(path),
+            (None, None) => BookDto::parse()
+        }?;
+
+        for overlay in &self.overlays {
+            book.apply_overlay(BookDto::from_file(overlay)?);
+        }
+        Ok(book)
+    }
+}
+
+#[derive(Parser, Debug)]
+pub struct NameArgs {
+    //Display the ids of the items and recipes instead of their names
+    #[clap(long)]
+    ids: bool,
+
+    //Display the names in the given locale (like 'fr' or 'pt_BR') when the book provides them
+    #[clap(long, env = "SATISBOM_LOCALE")]
+    locale: Option<String>,
+}
+
+impl NameArgs {
+    pub fn name_format(&self) -> NameFormat {
+        if self.ids {
+            NameFormat::Ids
+        } else {
+            NameFormat::DisplayNames(self.locale.clone())
+        }
+    }
 }
 
 #[derive(Parser, Debug)]
 pub struct DumpArg {
     #[clap(short, long)]
     output_file: Option<String>,
+
+    #[clap(flatten)]
+    book: BookArgs,
 }
 
 #[derive(Parser, Debug)]
 pub struct SearchArgs {
     pattern: String,
+
+    #[clap(flatten)]
+    book: BookArgs,
+
+    #[clap(flatten)]
+    names: NameArgs,
+}
+
+#[derive(Parser, Debug)]
+pub struct CheckBookArgs {
+    #[clap(flatten)]
+    book: BookArgs,
+}
+
+#[derive(Parser, Debug)]
+pub struct RankAlternatesArgs {
+    #[clap(flatten)]
+    book: BookArgs,
+
+    #[clap(flatten)]
+    names: NameArgs,
+
+    //Read production parameters from a JSON file
+    #[clap(short, long)]
+    input_file: Option<String>,
+
+    #[clap(short, long)]
+    filters: Option<String>,
+
+    //The items to produce, with the same syntax as the bom subcommand. They replace those of the input file
+    reactants: Vec<String>,
+}
+
+#[derive(Parser, Debug)]
+pub struct BookDiffArgs {
+    //The book to compare from : a JSON or XML file, or the version of an embedded book
+    old_book: String,
+
+    //The book to compare to : a JSON or XML file, or the version of an embedded book
+    new_book: String,
+
+    #[clap(short = 'F', long, default_value_t = DiffFormat::Text, arg_enum)]
+    format: DiffFormat,
+}
+
+#[derive(Debug, clap::ArgEnum, Clone)]
+pub enum DiffFormat {
+    Text,
+    Json,
+}
+
+impl Display for DiffFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DiffFormat::Text => write!(f, "text"),
+            DiffFormat::Json => write!(f, "json"),
+        }
+    }
+}
+
+#[derive(Parser, Debug)]
+pub struct ImportDocsArgs {
+    //Path to the Docs.json file (found in the CommunityResources folder of the game)
+    docs_file: String,
+
+    #[clap(short, long)]
+    output_file: Option<String>,
+
+    //The name of the generated book
+    #[clap(short, long, default_value = "Docs")]
+    name: String,
+
+    //The resource node counts are taken from this book since they are not part of Docs.json
+    #[clap(flatten)]
+    book: BookArgs,
 }
 
 #[derive(Parser, Debug)]
 pub struct BomArg {
 
+    #[clap(flatten)]
+    book: BookArgs,
+
+    #[clap(flatten)]
+    names: NameArgs,
+
+    //The items already available, separated by commas, like '30:iron_ingot,0.5/s:screw'
     #[clap(short, long)]
     available_items: Option<String>,
 
@@ -80,6 +246,60 @@ pub struct BomArg {
     #[clap(short = 'F', default_value_t = Format::Text, arg_enum)]
     format: Format,
 
+    /// How the factory is powered : 'external' (no constraint), 'self-powered' (the plan includes the generators
+    /// and their fuel) or the maximal power in MW taken from the grid. This is the net power : the consumption of
+    /// the buildings and extractors minus the production of the generators
+    #[clap(short = 'P', long)]
+    power: Option<String>,
+
+    /// The maximal power in MW consumed by the buildings and extractors of the factory. This is the gross power :
+    /// the generators are not deducted, so it also applies with '-P self-powered' or a grid budget
+    #[clap(long)]
+    max_power: Option<f64>,
+
+    //What the solver minimizes : 'resources', 'buildings', 'power', 'waste', 'recipes', 'shards' or a weighted sum like 'resources+0.5*power'
+    #[clap(short = 'O', long)]
+    objective: Option<String>,
+
+    //The clock speed in percent (1 to 250) of all the buildings
+    #[clap(short = 'c', long)]
+    clock_speed: Option<f64>,
+
+    //The clock speed of the buildings of a recipe, as 'recipe=percent'. Can be repeated
+    #[clap(long, multiple_occurrences = true)]
+    recipe_clock: Vec<String>,
+
+    //The max amount per minute extracted of a resource, as 'resource=rate' like 'iron_ore=480'. Can be repeated
+    #[clap(long, multiple_occurrences = true)]
+    cap: Vec<String>,
+
+    //The cost of a unit of a resource, as 'resource=weight', instead of its abundance weight. Can be repeated
+    #[clap(long, multiple_occurrences = true)]
+    weight: Vec<String>,
+
+    //What becomes of the products that are neither targets nor consumed : 'allow', 'forbid',
+    //'penalize' (or 'penalize:cost' per unit) or 'sink' (sent to an AWESOME Sink)
+    #[clap(long)]
+    byproducts: Option<String>,
+
+    //The policy of a byproduct, as 'item=policy'. Can be repeated
+    #[clap(long, multiple_occurrences = true)]
+    byproduct: Vec<String>,
+
+    //Solve with whole buildings : each recipe gets a whole number of machines, underclocked to run its exact amount,
+    //instead of rounding them up afterwards (slower)
+    #[clap(long)]
+    integer_buildings: bool,
+
+    //Produce as much as possible : the reactants are ratios (like '2.rotor 1.stator') scaled up to the resource limits
+    #[clap(short = 'M', long)]
+    maximize: bool,
+
+    //Report the resource limits, available items and power constraints that limit the solution, with the
+    //measured change of the objective per unit of each (solves the problem again with one extra unit of each)
+    #[clap(long)]
+    sensitivity: bool,
+
     //Read production parameters from a JSON file
     #[clap(short, long)]
     input_file: Option<String>,
@@ -103,6 +323,8 @@ pub struct BomArg {
     #[clap(short = 'x', long)]
     replace_reactants: bool,
 
+    //The items to produce, like '7.5:iron_plate', '2/s:screw', '10..15:computer' (between 10 and 15 per minute)
+    //or '10+:computer' (at least 10 per minute). The legacy format '30.iron_plate' is still accepted
     reactants: Vec<String>,
 }
 
@@ -136,21 +358,63 @@ impl BomArg {
             .unwrap_or(Ok(None))
     }
 
-    fn parsed_reactants(&self) -> Result<HashMap<String, u32>> {
+    fn parsed_power(&self) -> Result<Option<PowerSetting>> {
+        self.power.as_ref()
+            .map(|p| p.parse::<PowerSetting>())
+            .transpose()
+    }
+
+    fn parsed_objective(&self) -> Result<Option<Objective>> {
+        self.objective.as_ref()
+            .map(|o| o.parse::<Objective>())
+            .transpose()
+    }
+
+    fn parsed_recipe_clocks(&self) -> Result<HashMap<String, f64>> {
+        self.recipe_clock.iter()
+            .map(|c| c.split_once('=')
+                .and_then(|(recipe, percent)| percent.trim().parse::<f64>().ok().map(|p| (recipe.trim().to_string(), p)))
+                .ok_or_else(|| Error::ClockSpeedParsingFailed(c.clone())))
+            .collect()
+    }
+
+    fn parsed_caps(&self) -> Result<HashMap<String, Rate>> {
+        self.cap.iter().map(|c| parse_resource_setting(c)).collect()
+    }
+
+    fn parsed_weights(&self) -> Result<HashMap<String, f64>> {
+        self.weight.iter().map(|w| parse_resource_setting(w)).collect()
+    }
+
+    fn parsed_byproducts(&self) -> Result<Option<ByproductPolicy>> {
+        self.byproducts.as_deref()
+            .map(|p| p.parse::<ByproductPolicy>())
+            .transpose()
+    }
+
+    fn parsed_byproduct_policies(&self) -> Result<HashMap<String, ByproductPolicy>> {
+        self.byproduct.iter()
+            .map(|b| b.split_once('=')
+                .ok_or_else(|| Error::ByproductPolicyParsingFailed(b.clone()))
+                .and_then(|(item, policy)| policy.parse::<ByproductPolicy>().map(|p| (item.trim().to_string(), p))))
+            .collect()
+    }
+
+    fn parsed_reactants(&self) -> Result<HashMap<String, TargetAmount>> {
         self.reactants.iter()
-            .map(|r| r.parse::<InputItem>())
-            .map(|r| r.map(|i| (i.name, i.quantity)))
-            .collect::<Result<HashMap<String, u32>>>()
+            .map(|r| r.parse::<InputTarget>())
+            .map(|r| r.map(|t| (t.name, t.amount)))
+            .collect::<Result<HashMap<String, TargetAmount>>>()
     }
 
-    fn parsed_available_items(&self) -> Result<Option<HashMap<String, u32>>> {
+    fn parsed_available_items(&self) -> Result<Option<HashMap<String, Rate>>> {
         match &self.available_items {
             None => Ok(None),
             Some(items) => {
                 items.split(',')
                     .map(|r| r.parse::<InputItem>())
                     .map(|r| r.map(|i| (i.name, i.quantity)))
-                    .collect::<Result<HashMap<String, u32>>>()
+                    .collect::<Result<HashMap<String, Rate>>>()
                     .map(Some)
             }
         }
@@ -208,23 +472,62 @@ fn main() -> crate::error::Result<()> {
 
     match args.command {
         Command::Dump(d) => dump(d),
-        Command::Bom(b) => bom(b),
-        Command::Search(s) => search(s)
+        Command::Bom(b) => bom(*b).map_err(|e| match e {
+            //the diagnosis spans several lines and is meant to be read as is
+            Error::Infeasible(diagnosis) => {
+                eprintln!("{}", diagnosis);
+                std::process::exit(1)
+            }
+            e => e
+        }),
+        Command::Search(s) => search(s),
+        Command::ImportDocs(i) => import_docs(i),
+        Command::Books => books(),
+        Command::CheckBook(c) => check_book(c),
+        Command::BookDiff(d) => book_diff(d),
+        Command::RankAlternates(r) => rank_alternates(r),
     }
 }
 
 pub struct InputItem {
     pub name: String,
-    pub quantity: u32,
+    pub quantity: Rate,
 }
 
+/// Parse `rate:name` like `7.5/min:iron_plate`, or the legacy `count.name`
 impl FromStr for InputItem {
     type Err = Error;
 
     fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
-        let (qty, name) = s.split_once(".").unwrap_or(("1", s));
+        if let Some((rate, name)) = s.split_once(':') {
+            return Ok(InputItem { name: name.to_string(), quantity: rate.parse()? });
+        }
+        let (qty, name) = s.split_once('.').unwrap_or(("1", s));
         let quantity = qty.parse::<u32>().map_err(|_| Error::TargetParsingFailed(s.to_string()))?;
-        Ok(InputItem { name: name.to_string(), quantity })
+        Ok(InputItem { name: name.to_string(), quantity: Rate::per_minute(quantity as i64) })
+    }
+}
+
+pub struct InputTarget {
+    pub name: String,
+    pub amount: TargetAmount,
+}
+
+/// Parse `amount:name` like `2/s:screw` or `10..15:computer`. In the legacy format, the amount
+/// is made of the leading digits, dots and '+' up to the last dot before the name
+impl FromStr for InputTarget {
+    type Err = Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        if let Some((amount, name)) = s.split_once(':') {
+            return Ok(InputTarget { name: name.to_string(), amount: amount.parse()? });
+        }
+        let prefix_length = s.find(|c: char| !c.is_ascii_digit() && c != '.' && c != '+').unwrap_or(s.len());
+        let (amount, name) = match s[..prefix_length].rfind('.') {
+            Some(dot) => (s[..dot].parse::<TargetAmount>()?, &s[dot + 1..]),
+            None => (TargetAmount::Exact(Rate::per_minute(1)), s),
+        };
+        Ok(InputTarget { name: name.to_string(), amount })
     }
 }
 
@@ -241,6 +544,13 @@ fn bom(args: BomArg) -> crate::error::Result<()> {
     let available_items = args.parsed_available_items()?;
     let reactants = args.parsed_reactants()?;
     let filters = args.parsed_filters()?;
+    let power = args.parsed_power()?;
+    let objective = args.parsed_objective()?;
+    let recipe_clocks = args.parsed_recipe_clocks()?;
+    let caps = args.parsed_caps()?;
+    let weights = args.parsed_weights()?;
+    let byproducts = args.parsed_byproducts()?;
+    let byproduct_policies = args.parsed_byproduct_policies()?;
 
     if let Some(ua) = args.weight_by_abundance() {
         input.use_abundances = ua;
@@ -251,8 +561,11 @@ fn bom(args: BomArg) -> crate::error::Result<()> {
             input.target_items = reactants;
         } else {
             for (item, amount) in reactants {
-                let input_amount = input.target_items.entry(item).or_default();
-                *input_amount += amount;
+                let combined = match input.target_items.get(&item) {
+                    Some(input_amount) => input_amount.combine(amount),
+                    None => amount
+                };
+                input.target_items.insert(item, combined);
             }
         }
     }
@@ -268,18 +581,58 @@ fn bom(args: BomArg) -> crate::error::Result<()> {
         input.filter = filter
     }
 
+    if let Some(power) = power {
+        input.power = power
+    }
+
+    if let Some(max_power) = args.max_power {
+        input.max_power = Some(max_power)
+    }
+
+    if let Some(objective) = objective {
+        input.objective = objective
+    }
+
+    if let Some(clock_speed) = args.clock_speed {
+        input.clock_speed = Some(clock_speed)
+    }
+
+    input.recipe_clock_speeds.extend(recipe_clocks);
+    input.resource_caps.extend(caps);
+    input.resource_weights.extend(weights);
+
+    if let Some(byproducts) = byproducts {
+        input.byproducts = byproducts
+    }
+    input.byproduct_policies.extend(byproduct_policies);
+
+    if args.integer_buildings {
+        input.integer_buildings = true
+    }
+
+    if args.maximize {
+        input.maximize = true
+    }
+
+    let book = args.book.load()?;
+    if let Some(input_book) = input.book.as_ref().filter(|b| !b.eq_ignore_ascii_case(book.identifier())) {
+        eprintln!("Warning: the input was written against the book '{}' but the book '{}' is used", input_book, book.identifier());
+    }
 
     if let Some(dump_file) = args.dump_file().as_ref() {
+        input.book = Some(book.identifier().to_string());
         let file = File::create(dump_file)?;
         serde_json::to_writer_pretty(file, &input)?;
         Ok(())
     } else {
-        let bom = Bom::optimized(&input)?;
+        let bom = Bom::optimized(&input, &book)?;
+        let bottlenecks = if args.sensitivity { Some(sensitivity::bottlenecks(&input, &book)?) } else { None };
 
         let amount_format = if *args.use_ratio() { AmountFormat::Ratio } else { AmountFormat::F64 };
+        let name_format = args.names.name_format();
 
         if args.output_file().is_some() && args.force_stdout() {
-            let mut printer = BomPrinter::with_term(amount_format);
+            let mut printer = BomPrinter::with_term(amount_format, name_format.clone());
             bom.display(&mut printer)?;
         }
 
@@ -287,15 +640,20 @@ fn bom(args: BomArg) -> crate::error::Result<()> {
         match &args.format {
             Format::Text => {
                 let mut printer = if let Some(f) = args.output_file() {
-                    BomPrinter::with_file(File::create(format!("{}.txt", f))?, amount_format)
+                    BomPrinter::with_file(File::create(format!("{}.txt", f))?, amount_format, name_format)
                 } else {
-                    BomPrinter::with_term(amount_format)
+                    BomPrinter::with_term(amount_format, name_format)
                 };
 
-                bom.display(&mut printer)
+                bom.display(&mut printer)?;
+                match &bottlenecks {
+                    Some(bottlenecks) => printer.display_bottlenecks(bottlenecks),
+                    None => Ok(())
+                }
             }
             Format::Dot => {
-                let graph: Graph = Graph::new(&bom, amount_format);
+                display_bottlenecks_on_term(&bottlenecks, args.output_file(), amount_format, &name_format)?;
+                let graph: Graph = Graph::new(&bom, amount_format, name_format);
 
                 if let Some(f) = args.output_file() {
                     let mut file = File::create(format!("{}.dot", f))?;
@@ -308,7 +666,8 @@ fn bom(args: BomArg) -> crate::error::Result<()> {
                 Ok(())
             }
             Format::Png|Format::Svg => {
-                let graph: Graph = Graph::new(&bom, amount_format);
+                display_bottlenecks_on_term(&bottlenecks, args.output_file(), amount_format, &name_format)?;
+                let graph: Graph = Graph::new(&bom, amount_format, name_format);
                 let named_file = NamedTempFile::new()?;
                 dot::render(&graph, &mut named_file.as_file())?;
 
@@ -344,24 +703,52 @@ fn parse_filter(filter_str: &str) -> crate::error::Result<RecipeFilter> {
     Ok(AllOf(filters))
 }
 
+/// Parse a 'resource=value' setting
+fn parse_resource_setting<T: FromStr>(setting: &str) -> Result<(String, T)> {
+    setting.split_once('=')
+        .and_then(|(resource, value)| value.trim().parse::<T>().ok().map(|v| (resource.trim().to_string(), v)))
+        .ok_or_else(|| Error::ResourceSettingParsingFailed(setting.to_string()))
+}
+
 fn read_input(input_file: &str) -> crate::error::Result<ProblemInput> {
     let content = read_to_string(input_file)?;
     let input = serde_json::from_str::<ProblemInput>(&content)?;
     Ok(input)
 }
 
+/// Without an output file, the graph is written on the standard output and the report would corrupt it
+fn display_bottlenecks_on_term(bottlenecks: &Option<Vec<sensitivity::Bottleneck>>, output_file: &Option<String>, amount_format: AmountFormat, name_format: &NameFormat) -> Result<()> {
+    match bottlenecks {
+        Some(bottlenecks) if output_file.is_some() => BomPrinter::with_term(amount_format, name_format.clone()).display_bottlenecks(bottlenecks),
+        _ => Ok(())
+    }
+}
+
 fn dump(args: DumpArg) -> crate::error::Result<()> {
 
+    let book = args.book.load()?;
     let input = ProblemInput {
+        book: Some(book.identifier().to_string()),
         target_items: hashmap! {
-             "iron_plate".to_string() => 30,
-             "iron_rod".to_string() => 30,
+             "iron_plate".to_string() => TargetAmount::Exact(Rate::per_minute(30)),
+             "iron_rod".to_string() => TargetAmount::Range { min: Rate::per_minute(20), max: Some(Rate::per_minute(30)) },
         },
         available_items: hashmap! {
-            "iron_ingot".to_string() => 30,
+            "iron_ingot".to_string() => Rate::per_minute(30),
         },
         use_abundances: true,
+        resource_caps: HashMap::new(),
+        resource_weights: HashMap::new(),
         filter: AllOf(vec![NotAlternate, NotManual, NotNamed("copper_ingot".to_string()),NotNamed("Caterium_ingot".to_string())]),
+        power: PowerSetting::External,
+        max_power: None,
+        objective: Objective::default(),
+        clock_speed: None,
+        recipe_clock_speeds: HashMap::new(),
+        integer_buildings: false,
+        maximize: false,
+        byproducts: ByproductPolicy::Allow,
+        byproduct_policies: HashMap::new(),
     };
 
     match args.output_file {
@@ -381,39 +768,249 @@ fn dump(args: DumpArg) -> crate::error::Result<()> {
 
 fn search(search_args: SearchArgs) -> crate::error::Result<()> {
     let pattern = search_args.pattern;
-    let book = FullBook::create()?;
+    let book = search_args.book.load()?;
+    let name_format = search_args.names.name_format();
+    //the pattern matches the ids as before, or the displayed names ignoring the case
+    let lowercase_pattern = pattern.to_lowercase();
+    let matches = |id: &str, name: &str| id.contains(&pattern) || name.to_lowercase().contains(&lowercase_pattern);
 
-    let recipes: Vec<Recipe> = book.recipes().iter().filter(|r| r.id().contains(&pattern)).cloned().collect();
+    let recipes: Vec<Recipe> = book.recipes().iter().filter(|r| matches(r.id(), name_format.recipe(r))).cloned().collect();
     let recipes = sort_recipes(recipes);
 
-    let mut writer = BomPrinter::with_term(AmountFormat::F64);
+    let mut writer = BomPrinter::with_term(AmountFormat::F64, name_format.clone());
 
     let mut should_display_header = true;
     for recipe in recipes {
-        if recipe.id().contains(&pattern) {
-            if should_display_header {
-                writer.reset()?;
-                should_display_header = false;
-                writeln!(writer, "=== Recipe ===")?;
-            }
+        if should_display_header {
             writer.reset()?;
-            write!(writer, "{:<25}  : ", recipe.id())?;
-            writer.display_recipe(&recipe, 1f64)?;
-            writeln!(writer)?;
+            should_display_header = false;
+            writeln!(writer, "=== Recipe ===")?;
         }
+        writer.reset()?;
+        write!(writer, "{:<36}  : ", name_format.recipe(&recipe))?;
+        writer.display_recipe(&recipe, Rate::per_minute(1))?;
+        display_overlay(&mut writer, book.recipe_overlay(recipe.id()))?;
+        writeln!(writer)?;
     }
 
     let mut should_display_header = true;
-    for item in book.items().keys() {
-        if item.contains(&pattern) {
+    for item in book.items().values() {
+        if matches(item.id(), name_format.item(item)) {
             if should_display_header {
                 should_display_header = false;
                 writer.reset()?;
                 writeln!(writer, "=== Items ===")?;
             }
-            writeln!(writer, " {}", item)?;
+            write!(writer, " {}", name_format.item(item))?;
+            display_item_metadata(&mut writer, item)?;
+            display_overlay(&mut writer, book.item_overlay(item.id()))?;
+            writeln!(writer)?;
         }
     }
 
     Ok(())
 }
+
+fn display_item_metadata(writer: &mut BomPrinter, item: &Item) -> crate::error::Result<()> {
+    let metadata = item.metadata();
+    let mut details = vec![];
+    if let Some(stack_size) = metadata.stack_size() {
+        details.push(format!("stack: {}", stack_size));
+    }
+    if let Some(sink_points) = metadata.sink_points() {
+        details.push(format!("sink: {} pts", sink_points));
+    }
+    if let Some(energy) = metadata.energy() {
+        let unit = if item.is_fluid() { "MJ/m³" } else { "MJ" };
+        details.push(format!("energy: {} {}", energy, unit));
+    }
+    if let Some(radioactivity) = metadata.radioactivity() {
+        details.push(format!("radioactivity: {}", radioactivity));
+    }
+
+    if !details.is_empty() {
+        write!(writer, " ({})", details.join(", "))?;
+    }
+    Ok(())
+}
+
+fn display_overlay(writer: &mut BomPrinter, overlay: Option<&str>) -> crate::error::Result<()> {
+    if let Some(overlay) = overlay {
+        writer.fg(OVERLAY_COLOR)?;
+        write!(writer, " [overlay {}]", overlay)?;
+        writer.reset()?;
+    }
+    Ok(())
+}
+
+
+fn import_docs(args: ImportDocsArgs) -> crate::error::Result<()> {
+    let content = std::fs::read(&args.docs_file)?;
+    let nodes_from = args.book.load_dto()?;
+    let book = docs_import::import_docs(&content, &args.name, &nodes_from)?;
+
+    match args.output_file {
+        None => {
+            let result = serde_json::to_string_pretty(&book)?;
+            println!("{}", result);
+            Ok(())
+        }
+        Some(file_name) => {
+            let file = File::create(file_name)?;
+            serde_json::to_writer_pretty(file, &book)?;
+            Ok(())
+        }
+    }
+}
+
+
+fn books() -> crate::error::Result<()> {
+    let mut writer = BomPrinter::with_term(AmountFormat::F64, NameFormat::Ids);
+    writer.reset()?;
+    writeln!(writer, "{:<12} {:<15} {:>8} {:>6} {:>10}", "Version", "Name", "Recipes", "Items", "Buildings")?;
+    for embedded_book in &EMBEDDED_BOOKS {
+        let book = BookDto::embedded(embedded_book.version)?;
+        let default = if std::ptr::eq(embedded_book, DEFAULT_BOOK) { " (default)" } else { "" };
+        writeln!(writer, "{:<12} {:<15} {:>8} {:>6} {:>10}{}",
+                 embedded_book.version, book.name, book.recipes.len(), book.items.len(), book.buildings.len(), default)?;
+    }
+    Ok(())
+}
+
+
+fn check_book(args: CheckBookArgs) -> crate::error::Result<()> {
+    let book = args.book.load_dto()?;
+    let findings = book_check::check_book(&book);
+
+    let mut writer = BomPrinter::with_term(AmountFormat::F64, NameFormat::Ids);
+    for finding in &findings {
+        writer.fg(match finding.severity {
+            Severity::Error => ERROR_COLOR,
+            Severity::Warning => WARNING_COLOR
+        })?;
+        write!(writer, "{:<7}", finding.severity)?;
+        writer.reset()?;
+        writeln!(writer, " {} '{}' {}", finding.subject, finding.id, finding.message)?;
+    }
+
+    let nb_errors = findings.iter().filter(|f| f.severity == Severity::Error).count();
+    if nb_errors > 0 {
+        return Err(Error::InvalidBook(nb_errors));
+    }
+
+    //the checks should catch anything that would prevent the conversion but make sure of it
+    book.to_full_book()?;
+    writer.reset()?;
+    writeln!(writer, "No error found ({} warning(s))", findings.len())?;
+    Ok(())
+}
+
+
+fn rank_alternates(args: RankAlternatesArgs) -> crate::error::Result<()> {
+    if args.input_file.is_none() && args.reactants.is_empty() {
+        return Err(Clap(MissingRequiredArgument));
+    };
+
+    let mut input = args.input_file.as_ref()
+        .map(|f| read_input(f))
+        .unwrap_or_else(|| Ok(ProblemInput::default()))?;
+
+    if !args.reactants.is_empty() {
+        input.target_items = args.reactants.iter()
+            .map(|r| r.parse::<InputTarget>().map(|t| (t.name, t.amount)))
+            .collect::<Result<HashMap<String, TargetAmount>>>()?;
+    }
+    if let Some(filters) = args.filters.as_ref() {
+        input.filter = parse_filter(filters)?;
+    }
+
+    let book = args.book.load()?;
+    let name_format = args.names.name_format();
+    let values = alternate_ranking::rank_alternates(&input, &book)?;
+
+    let mut writer = BomPrinter::with_term(AmountFormat::F64, name_format.clone());
+    writer.reset()?;
+    let gain = if input.maximize { "Scale" } else { "Objective" };
+    writeln!(writer, "  {:<36} {:>10} {:>10} {:>10}", "Alternate", gain, "Resources", "Buildings")?;
+    writeln!(writer, "--------------------------------------------------------------------------")?;
+    for value in &values {
+        writer.fg(RECIPE_NAME_COLOR)?;
+        write!(writer, "  {:<36}", name_format.recipe(&value.recipe))?;
+        writer.fg(AMOUNT_COLOR)?;
+        writeln!(writer, " {:>10} {:>10} {:>10}",
+                 AmountFormat::F64.format(&value.objective_gain),
+                 AmountFormat::F64.format(&value.resource_savings),
+                 value.building_savings)?;
+    }
+    writer.reset()?;
+    if values.is_empty() {
+        writeln!(writer, "No alternate is kept by the filters")?;
+    }
+    Ok(())
+}
+
+fn book_diff(args: BookDiffArgs) -> crate::error::Result<()> {
+    let old_book = BookDto::from_file_or_embedded(&args.old_book)?;
+    let new_book = BookDto::from_file_or_embedded(&args.new_book)?;
+    let diff = book_diff::diff_books(&old_book, &new_book);
+
+    match args.format {
+        DiffFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(&diff)?);
+            Ok(())
+        }
+        DiffFormat::Text => display_book_diff(&diff)
+    }
+}
+
+fn display_book_diff(diff: &BookDiff) -> crate::error::Result<()> {
+    let mut writer = BomPrinter::with_term(AmountFormat::F64, NameFormat::Ids);
+    if diff.is_empty() {
+        writer.reset()?;
+        writeln!(writer, "The books are identical")?;
+        return Ok(());
+    }
+
+    let sections = [
+        ("Items", &diff.added_items, &diff.removed_items),
+        ("Buildings", &diff.added_buildings, &diff.removed_buildings),
+        ("Recipes", &diff.added_recipes, &diff.removed_recipes),
+    ];
+    for (header, added, removed) in sections {
+        if added.is_empty() && removed.is_empty() {
+            continue;
+        }
+        writer.reset()?;
+        writeln!(writer, "=== {} ===", header)?;
+        for (sign, color, ids) in [("+", ADDED_COLOR, added), ("-", REMOVED_COLOR, removed)] {
+            for id in ids {
+                writer.fg(color)?;
+                writeln!(writer, " {} {}", sign, id)?;
+            }
+        }
+    }
+
+    if !diff.changed_recipes.is_empty() {
+        writer.reset()?;
+        writeln!(writer, "=== Changed recipes ===")?;
+    }
+    for recipe in &diff.changed_recipes {
+        writer.fg(CHANGED_COLOR)?;
+        writeln!(writer, " ~ {}", recipe.id)?;
+        writer.reset()?;
+        for change in &recipe.changes {
+            match change {
+                RecipeChange::Duration { old, new } => writeln!(writer, "     duration  : {} -> {}", old, new),
+                RecipeChange::Building { old, new } => writeln!(writer, "     building  : {} -> {}", old, new),
+                RecipeChange::Alternate { old, new } => writeln!(writer, "     alternate : {} -> {}", old, new),
+                RecipeChange::Input { item, old, new } => writeln!(writer, "     input     : {} {} -> {}", item, format_quantity(old), format_quantity(new)),
+                RecipeChange::Output { item, old, new } => writeln!(writer, "     output    : {} {} -> {}", item, format_quantity(old), format_quantity(new)),
+            }?;
+        }
+    }
+    Ok(())
+}
+
+fn format_quantity(quantity: &Option<u32>) -> String {
+    quantity.map(|q| q.to_string()).unwrap_or_else(|| "none".to_string())
+}
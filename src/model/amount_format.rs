@@ -1,6 +1,7 @@
-use std::ops::{Add, Neg};
-use num_rational::Ratio;
-use num_traits::Inv;
+use crate::model::rate::Rate;
+
+/// The largest denominator of the fractions shown for the approximate amounts, the others are decimals
+const MAX_SHOWN_DENOMINATOR: i64 = 1000;
 
 #[derive(Copy, Clone)]
 pub enum AmountFormat {
@@ -9,74 +10,44 @@ pub enum AmountFormat {
 }
 
 impl AmountFormat {
+    /// Format an approximate amount, like a number of buildings or a change of the objective.
+    /// The ratio format shows the simple fraction it stands for, or a decimal when there is none
     pub fn format(&self, amount: &f64) -> String {
-        let ratio = ratio_approximate(*amount);
+        let rate = Rate::snapped(*amount);
         match self {
-            AmountFormat::F64 => {
-                if ratio.is_integer() {
-                    ratio.to_string()
-                } else {
-                    format!("{}", (amount * 1000f64).round() / 1000f64)
-                }
-            },
-            AmountFormat::Ratio => ratio.to_string()
+            _ if !amount.is_finite() => decimal(*amount),
+            AmountFormat::Ratio if *rate.ratio().denom() <= MAX_SHOWN_DENOMINATOR => rate.to_string(),
+            _ if rate.ratio().is_integer() => rate.to_string(),
+            _ => decimal(*amount),
         }
     }
 
-}
-
-
-fn ratio_approximate(value:f64) -> Ratio<i32> {
-    if value < 0f64 {
-        ratio_approximate(-value).neg()
-    } else {
-        let i = value.round();
-        let r = do_ratio_approximate(value - i);
-        Ratio::from_integer(i as i32).add(r)
-    }
-}
-
-fn do_ratio_approximate(value:f64) -> Ratio<i32> {
-    if value < 0f64 {
-        return do_ratio_approximate(-value).neg();
-    }
-    let mut val = value;
-    let mut coef = [0u32;1000];
-    for i in 0..coef.len() {
-        let e = val.floor() as u32;
-        let r = val-(e as f64);
-        coef[i] = e;
-
-        let estimation = estimate(&coef,i+1);
-        if (estimation-value).abs() < 1e-3 {
-            return compute_ratio(&coef,i+1);
+    /// Format an exact amount, the ratio format shows its true value
+    pub fn format_rate(&self, amount: &Rate) -> String {
+        match self {
+            AmountFormat::F64 if !amount.ratio().is_integer() => decimal(amount.as_f64()),
+            _ => amount.to_string()
         }
-
-        val = 1./r;
-    };
-
-    todo!()
+    }
 
 }
 
-fn estimate(coef:&[u32], length:usize) -> f64 {
-
-    let mut r = coef[length-1] as f64;
-    for i in (0..length-1).rev() {
-        r = (coef[i] as f64) + 1f64/r;
-    };
-
-    r
+fn decimal(amount: f64) -> String {
+    format!("{}", (amount * 1000f64).round() / 1000f64)
 }
 
-fn compute_ratio(coef:&[u32], length:usize) -> Ratio<i32> {
-    let mut r = Ratio::from_integer(coef[length-1] as i32);
-
-    for i in (0..length-1).rev() {
-        r = r.inv().add(coef[i] as i32);
-    };
-
-    r
 
+#[cfg(test)]
+mod tests {
+    use crate::model::amount_format::AmountFormat;
 
+    #[test]
+    fn approximate_amounts() {
+        assert_eq!(AmountFormat::Ratio.format(&(4f64 / 3f64)), "4/3");
+        assert_eq!(AmountFormat::F64.format(&(4f64 / 3f64)), "1.333");
+        assert_eq!(AmountFormat::F64.format(&2.00000001f64), "2");
+        //the amounts without a simple fraction, like the measured changes of the objective, are decimals
+        assert_eq!(AmountFormat::Ratio.format(&-1.8313f64), "-1.831");
+        assert_eq!(AmountFormat::Ratio.format(&std::f64::consts::SQRT_2), "1.414");
+    }
 }
@@ -1,88 +1,178 @@
 use std::collections::HashMap;
 
+use good_lp::ResolutionError;
 use hashlink::LinkedHashMap;
 
 use crate::{FilterableBook, FullBook, ProblemInput};
+use crate::diagnosis::diagnose;
+use crate::error::Error;
 use crate::factory::Factory;
+use crate::model::filtered_book::FilteredBook;
 use crate::model::bom_printer::BomPrinter;
 use crate::model::building::Building;
+use crate::model::clock_speed::{ClockSpeed, ClockSpeeds};
+use crate::model::extraction::{allocate_nodes, NodeAllocation};
 use crate::model::item::Item;
+use crate::model::rate::Rate;
 use crate::model::recipe::Recipe;
+use crate::problem_input::ByproductPolicy;
 use crate::model::recipe_complexity::compute_complexity;
+use crate::constants::is_nil;
+
+/// The maximal scale of the targets is backed off by one part in this when it is not feasible
+const SCALE_BACKOFF: i64 = 1_000_000;
 
 pub struct Bom {
-    pub targets: HashMap<Item, f64>,
-    pub available_items: HashMap<Item, f64>,
-    pub requirements: HashMap<Item, f64>,
-    pub leftovers: HashMap<Item, f64>,
-    pub recipes: LinkedHashMap<Recipe, f64>,
-    pub buildings: HashMap<Building, u32>,
+    pub targets: HashMap<Item, Rate>,
+    pub available_items: HashMap<Item, Rate>,
+    pub requirements: HashMap<Item, Rate>,
+    pub leftovers: HashMap<Item, Rate>,
+    pub recipes: LinkedHashMap<Recipe, Rate>,
+    pub buildings: HashMap<(Building, ClockSpeed), u32>,
+    pub clock_speeds: ClockSpeeds,
+    pub extraction: Vec<NodeAllocation>,
+    //the amounts of resources the extractors can not deliver from the nodes, even overclocked
+    pub unextracted: HashMap<Item, Rate>,
+    //the byproducts sent to an AWESOME Sink
+    pub sunk: HashMap<Item, Rate>,
+    //the policies of the byproducts of the plan, except the allowed ones
+    pub byproduct_policies: HashMap<Item, ByproductPolicy>,
+    //the value of the objective minimized by the solver
+    pub objective: f64,
 }
 
 impl Bom {
-    pub(crate) fn get_leftover_amount(&self, item: &Item) -> Option<&f64> {
+    pub(crate) fn get_leftover_amount(&self, item: &Item) -> Option<&Rate> {
         self.leftovers.get(item)
     }
-    pub(crate) fn get_targeted_amount(&self, item: &Item) -> Option<&f64> {
+    pub(crate) fn get_targeted_amount(&self, item: &Item) -> Option<&Rate> {
         self.targets.get(item)
     }
-    pub(crate) fn get_available_amount(&self, item: &Item) -> Option<&f64> {
+    pub(crate) fn get_available_amount(&self, item: &Item) -> Option<&Rate> {
         self.available_items.get(item)
     }
+    pub(crate) fn get_sunk_amount(&self, item: &Item) -> Option<&Rate> {
+        self.sunk.get(item)
+    }
 }
 
 impl Bom {
-    pub fn optimized(input: &ProblemInput) -> crate::error::Result<Self> {
-        let full_book = FullBook::create()?;
+    pub fn optimized(input: &ProblemInput, full_book: &FullBook) -> crate::error::Result<Self> {
         let book = full_book.filter(input.filter())?;
-        let problem = Factory::create_problem(input,&book)?;
+        Bom::solve(input, &book).map_err(|e| match e {
+            Error::ResolutionFailed(ResolutionError::Infeasible) => match diagnose(input, full_book, &book) {
+                Ok(diagnosis) => Error::Infeasible(diagnosis),
+                Err(e) => e,
+            },
+            e => e,
+        })
+    }
+
+    fn solve(input: &ProblemInput, book: &FilteredBook) -> crate::error::Result<Self> {
+        let problem = Factory::create_problem(input,book)?;
+        if input.maximize {
+            //find the largest production first, then the best way to reach it
+            //the scale is snapped to its exact value, the solver gives it as a f32
+            let scale = Rate::snapped(problem.maximal_scale()?);
+            if scale.is_zero() {
+                //nothing can be produced, let the diagnosis explain why
+                return Err(Error::ResolutionFailed(ResolutionError::Infeasible));
+            }
+            //the rounding of the solver can put the scale just out of reach, it is then backed off a little
+            return match Factory::create_problem(input,book)?.with_scale(scale).solve() {
+                Err(Error::ResolutionFailed(ResolutionError::Infeasible)) =>
+                    Factory::create_problem(input,book)?.with_scale(scale * Rate::new(SCALE_BACKOFF - 1, SCALE_BACKOFF)).solve(),
+                result => result,
+            };
+        }
         problem.solve()
     }
 }
 
 
 impl Bom {
-    pub fn new(targets: HashMap<Item, f64>,
-               available_items: HashMap<Item, f64>,
-               requirements: HashMap<Item, f64>,
-               leftovers: HashMap<Item, f64>,
-               recipes: HashMap<Recipe, f64>) -> Self {
+    pub fn new(targets: HashMap<Item, Rate>,
+               available_items: HashMap<Item, Rate>,
+               requirements: HashMap<Item, Rate>,
+               leftovers: HashMap<Item, Rate>,
+               recipes: HashMap<Recipe, Rate>,
+               clock_speeds: ClockSpeeds,
+               building_counts: HashMap<Recipe, u32>) -> Self {
         let mut buildings = HashMap::new();
 
         for (recipe, amount) in &recipes {
-            let building = recipe.building();
-            let q = (*amount / recipe.nb_per_minute()).ceil() as u32;
-            match buildings.get_mut(building) {
-                None => { buildings.insert(building.clone(), q); }
-                Some(a) => *a += q
-            }
+            let needed = clock_speeds.building_count(recipe, amount.as_f64());
+            let (clock_speed, q) = match building_counts.get(recipe) {
+                //the whole buildings of the integer mode are underclocked to run the exact amount
+                Some(count) if *count > 0 => (clock_speeds.of(recipe).underclocked(needed / *count as f64), *count),
+                _ => (clock_speeds.of(recipe), needed.ceil() as u32),
+            };
+            *buildings.entry((recipe.building().clone(), clock_speed)).or_insert(0) += q;
         }
 
 
+        let mut extraction: Vec<NodeAllocation> = vec![];
+        let mut unextracted = HashMap::new();
+        for (item, amount) in &requirements {
+            let (allocations, remainder) = allocate_nodes(item, amount.as_f64());
+            extraction.extend(allocations);
+            if !is_nil(remainder) {
+                unextracted.insert(item.clone(), Rate::snapped(remainder));
+            }
+        }
+        extraction.sort_by(|a1, a2| a1.resource.id().cmp(a2.resource.id()));
+
         let recipes = sort_recipes(recipes);
 
-        Bom { targets, available_items, requirements, leftovers, recipes, buildings }
+        Bom { targets, available_items, requirements, leftovers, recipes, buildings, clock_speeds, extraction, unextracted, sunk: HashMap::new(), byproduct_policies: HashMap::new(), objective: 0f64 }
+    }
+
+    pub fn with_byproducts(mut self, sunk: HashMap<Item, Rate>, byproduct_policies: HashMap<Item, ByproductPolicy>) -> Self {
+        self.sunk = sunk;
+        self.byproduct_policies = byproduct_policies;
+        self
+    }
+
+    pub fn with_objective(mut self, objective: f64) -> Self {
+        self.objective = objective;
+        self
+    }
+
+    /// The amount of resources extracted per minute
+    pub fn resource_usage(&self) -> f64 {
+        self.requirements.values().map(|r| r.as_f64()).sum()
+    }
+
+    pub fn building_count(&self) -> u32 {
+        self.buildings.values().sum()
+    }
+
+    /// The AWESOME Sink points earned per minute with the sunk byproducts
+    pub fn sink_points(&self) -> Rate {
+        self.sunk.iter()
+            .map(|(item, amount)| *amount * item.metadata().sink_points().unwrap_or(0))
+            .sum()
     }
 }
 
 pub struct ItemUsage<'a> {
     pub recipe:&'a Recipe,
-    pub quantity:f64,
+    pub quantity:Rate,
 }
 
 impl Bom {
 
-    pub fn get_all_items(&self) -> HashMap<&Item, (Vec<ItemUsage>, Vec<ItemUsage>)> {
+    pub fn get_all_items(&self) -> HashMap<&Item, (Vec<ItemUsage<'_>>, Vec<ItemUsage<'_>>)> {
         let mut result= HashMap::new();
 
         for (recipe,amount) in &self.recipes {
             for reactant in recipe.inputs() {
                 let value = result.entry(reactant.item()).or_insert_with(|| (vec![], vec![]));
-                value.0.push(ItemUsage{recipe, quantity:amount*reactant.quantity_f64()});
+                value.0.push(ItemUsage{recipe, quantity:*amount*reactant.quantity()});
             }
             for reactant in recipe.outputs() {
                 let value = result.entry(reactant.item()).or_insert_with(|| (vec![], vec![]));
-                value.1.push(ItemUsage{recipe, quantity:amount*reactant.quantity_f64()});
+                value.1.push(ItemUsage{recipe, quantity:*amount*reactant.quantity()});
             }
         }
 
@@ -96,13 +186,17 @@ impl Bom {
         bp.display_items("You need:", &self.requirements)?;
         bp.display_items("Leftovers:", &self.leftovers)?;
 
-        bp.display_recipes(&self.recipes)?;
+        bp.display_recipes(&self.recipes, &self.clock_speeds)?;
+
+        bp.display_buildings(&self.buildings)?;
+
+        bp.display_extraction(&self.extraction, &self.unextracted)?;
 
-        bp.display_buildings(&self.buildings)
+        bp.display_byproducts(self)
     }
 }
 
-fn sort_recipes(recipes: HashMap<Recipe, f64>) -> LinkedHashMap<Recipe,f64> {
+fn sort_recipes(recipes: HashMap<Recipe, Rate>) -> LinkedHashMap<Recipe,Rate> {
     let mut recipes_vec:Vec<Recipe> = recipes.keys().cloned().collect();
     let complexity = compute_complexity(&recipes_vec);
 
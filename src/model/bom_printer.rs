@@ -10,16 +10,26 @@ use term::StdoutTerminal;
 
 use crate::colors::{AMOUNT_COLOR, CONSTRUCTOR_COLOR, DURATION_COLOR, ITEM_COLOR, RECIPE_NAME_COLOR};
 use crate::model::amount_format::AmountFormat;
+use crate::model::bom::Bom;
 use crate::model::building::Building;
+use crate::model::clock_speed::{ClockSpeed, ClockSpeeds};
+use crate::model::extraction::NodeAllocation;
 use crate::model::item::Item;
+use crate::model::name_format::NameFormat;
+use crate::model::pipe::Pipe;
+use crate::model::rate::Rate;
 use crate::model::reactant::Reactant;
+use crate::problem::Relaxation;
+use crate::problem_input::ByproductPolicy;
 use crate::Recipe;
+use crate::sensitivity::Bottleneck;
 
 pub struct BomPrinter<'a> {
     file: Option<File>,
     writer: Option<&'a mut dyn std::fmt::Write>,
     term: Option<Box<StdoutTerminal>>,
     amount_format: AmountFormat,
+    name_format: NameFormat,
 }
 
 impl<'a> BomPrinter<'a> {
@@ -31,19 +41,19 @@ impl<'a> BomPrinter<'a> {
         }
     }
 
-    pub(crate) fn with_file(file:File, amount_format:AmountFormat) -> Self {
-        BomPrinter{file:Some(file), writer:None, term:None, amount_format}
+    pub(crate) fn with_file(file:File, amount_format:AmountFormat, name_format:NameFormat) -> Self {
+        BomPrinter{file:Some(file), writer:None, term:None, amount_format, name_format}
     }
 
 
     #[allow(dead_code)]
-    pub(crate) fn with_writer(writer:&'a mut dyn std::fmt::Write, amount_format:AmountFormat) -> Self {
-        BomPrinter{file:None, writer:Some(writer), term:None, amount_format}
+    pub(crate) fn with_writer(writer:&'a mut dyn std::fmt::Write, amount_format:AmountFormat, name_format:NameFormat) -> Self {
+        BomPrinter{file:None, writer:Some(writer), term:None, amount_format, name_format}
     }
 
 
-    pub fn with_term(amount_format: AmountFormat) -> Self {
-        BomPrinter{file:None, writer: None, term: Some(term::stdout().unwrap()), amount_format }
+    pub fn with_term(amount_format: AmountFormat, name_format: NameFormat) -> Self {
+        BomPrinter{file:None, writer: None, term: Some(term::stdout().unwrap()), amount_format, name_format }
     }
 
     pub fn reset(&mut self) -> term::Result<()> {
@@ -56,33 +66,128 @@ impl<'a> BomPrinter<'a> {
 }
 
 impl BomPrinter<'_> {
-    pub fn display_buildings(&mut self, buildings: &HashMap<Building, u32>) -> crate::error::Result<()> {
+    pub fn display_buildings(&mut self, buildings: &HashMap<(Building, ClockSpeed), u32>) -> crate::error::Result<()> {
         self.reset()?;
         writeln!(self, "=== Buildings ===")?;
 
-        let mut total = 0;
-        for (b, a) in buildings {
-            let power_needed = b.power_usage() * (*a as i32);
-            writeln!(self, "{:>8} - {:>13} ({:9} MW)", a, b.id(), power_needed)?;
-            total += power_needed
+        let mut total = 0f64;
+        let mut shards = 0;
+        for ((b, clock_speed), a) in buildings {
+            let power_needed = clock_speed.power_usage(b) * (*a as f64);
+            writeln!(self, "{:>8} - {:>13} @ {:>7} ({:9.1} MW)", a, b.id(), clock_speed, power_needed)?;
+            total += power_needed;
+            shards += clock_speed.power_shards() * a;
         };
 
-        writeln!(self, "{:>8}   {:>13} ({:9} MW)", "", "Total", total).map_err(crate::error::Error::Fmt)
+        writeln!(self, "{:>8}   {:>13}   {:>7} ({:9.1} MW)", "", "Total", "", total)?;
+        if shards > 0 {
+            writeln!(self, "{:>8} - Power Shard", shards)?;
+        }
+        Ok(())
+    }
+
+    pub fn display_extraction(&mut self, extraction: &[NodeAllocation], unextracted: &HashMap<Item, Rate>) -> crate::error::Result<()> {
+        if extraction.is_empty() && unextracted.is_empty() {
+            return Ok(());
+        }
+
+        self.reset()?;
+        writeln!(self, "=== Extraction ===")?;
+
+        let mut total = 0f64;
+        let mut shards = 0;
+        for allocation in extraction {
+            let nodes = match allocation.purity {
+                Some(purity) => format!("{} {} nodes", purity, self.name_format.item(&allocation.resource)),
+                None => self.name_format.item(&allocation.resource).to_string(),
+            };
+            let power_needed = allocation.power_usage();
+            writeln!(self, "{:>8} - {:>13} @ {:>7} on {:<30} ({:9.1} MW)", allocation.count, allocation.extractor.id(), allocation.clock_speed, nodes, power_needed)?;
+            total += power_needed;
+            shards += allocation.clock_speed.power_shards() * allocation.count;
+        }
+
+        writeln!(self, "{:>8}   {:>13}   {:>7}    {:<30} ({:9.1} MW)", "", "Total", "", "", total)?;
+        if shards > 0 {
+            writeln!(self, "{:>8} - Power Shard", shards)?;
+        }
+        for (item, amount) in unextracted {
+            writeln!(self, "  {}/min of {} can not be extracted from its nodes, even overclocked", self.amount_format.format_rate(amount), self.name_format.item(item))?;
+        }
+        Ok(())
+    }
+
+    /// The policy of each byproduct, with the points earned by the sunk ones
+    pub fn display_byproducts(&mut self, bom: &Bom) -> crate::error::Result<()> {
+        if bom.byproduct_policies.is_empty() {
+            return Ok(());
+        }
+
+        self.reset()?;
+        writeln!(self, "=== Byproducts ===")?;
+
+        let mut byproducts: Vec<(&Item, &ByproductPolicy)> = bom.byproduct_policies.iter().collect();
+        byproducts.sort_by(|(i1, _), (i2, _)| i1.id().cmp(i2.id()));
+        for (item, policy) in byproducts {
+            let amount = bom.get_sunk_amount(item)
+                .or_else(|| bom.get_leftover_amount(item))
+                .cloned()
+                .unwrap_or_default();
+            self.reset()?;
+            write!(self, "  {:<36} {:<12}", self.name_format.item(item), policy.to_string())?;
+            self.fg(AMOUNT_COLOR)?;
+            write!(self, " {:>8}/min", self.amount_format.format_rate(&amount))?;
+            if let (Some(_), Some(points)) = (bom.get_sunk_amount(item), item.metadata().sink_points()) {
+                write!(self, " ({:>8} points/min)", self.amount_format.format_rate(&(amount * points)))?;
+            }
+            writeln!(self)?;
+        }
+
+        if !bom.sunk.is_empty() {
+            self.reset()?;
+            writeln!(self, "  {:<36} {:<12} {:>8}     ({:>8} points/min)", "", "Total", "", self.amount_format.format_rate(&bom.sink_points()))?;
+        }
+        Ok(())
+    }
+
+    pub fn display_bottlenecks(&mut self, bottlenecks: &[Bottleneck]) -> crate::error::Result<()> {
+        self.reset()?;
+        writeln!(self, "=== Bottlenecks ===")?;
+        if bottlenecks.is_empty() {
+            writeln!(self, "  no resource limit, available item or power constraint is reached")?;
+        }
+
+        for bottleneck in bottlenecks {
+            let (constraint, unit) = match &bottleneck.constraint {
+                Relaxation::ResourceCap(item) => (format!("{} extraction", self.name_format.item(item)), "/min"),
+                Relaxation::AvailableItem(item) => (format!("available {}", self.name_format.item(item)), "/min"),
+                Relaxation::TargetMax(item) => (format!("max of {}", self.name_format.item(item)), "/min"),
+                Relaxation::Byproduct(item) => (format!("leftover of {}", self.name_format.item(item)), "/min"),
+                Relaxation::Power => ("power budget".to_string(), " MW"),
+                Relaxation::MaxPower => ("max power".to_string(), " MW"),
+            };
+            self.reset()?;
+            write!(self, "  {:<36}", constraint)?;
+            self.fg(AMOUNT_COLOR)?;
+            writeln!(self, " {:>10} measured change per 1{}", self.amount_format.format(&bottleneck.objective_change), unit)?;
+        }
+
+        Ok(())
     }
 
-    pub fn display_recipes(&mut self, recipes: &LinkedHashMap<Recipe, f64>) -> crate::error::Result<()> {
+    pub fn display_recipes(&mut self, recipes: &LinkedHashMap<Recipe, Rate>, clock_speeds: &ClockSpeeds) -> crate::error::Result<()> {
         self.reset()?;
         writeln!(self, "=== Recipes ===")?;
-        writeln!(self, "  {:>7} - {:<26} {:>3} {:>7} Detail", "#", "Name", "sec", "# Cons.")?;
-        writeln!(self, "---------------------------------------------------------")?;
+        writeln!(self, "  {:>7} - {:<36} {:>3} {:>7} Detail", "#", "Name", "sec", "# Cons.")?;
+        writeln!(self, "-------------------------------------------------------------------")?;
 
         for (recipe, amount) in recipes.iter() {
-            let nb_need = amount / recipe.nb_per_minute();
+            let nb_need = clock_speeds.building_count(recipe, amount.as_f64());
             self.reset()?;
-            write!(self, "  {:>7}", self.amount_format.format(amount))?;
+            write!(self, "  {:>7}", self.amount_format.format_rate(amount))?;
             write!(self, " - ")?;
             self.fg(RECIPE_NAME_COLOR)?;
-            write!(self, "{:<26}", recipe.id())?;
+            write!(self, "{:<36}", self.name_format.recipe(recipe))?;
             self.fg(DURATION_COLOR)?;
             write!(self, " {:>3}", recipe.duration())?;
             self.fg(CONSTRUCTOR_COLOR)?;
@@ -94,7 +199,7 @@ impl BomPrinter<'_> {
         Ok(())
     }
 
-    pub fn display_items(&mut self, header: &str, items: &HashMap<Item, f64>) -> crate::error::Result<()> {
+    pub fn display_items(&mut self, header: &str, items: &HashMap<Item, Rate>) -> crate::error::Result<()> {
         if items.is_empty() {
             return Ok(());
         }
@@ -102,14 +207,19 @@ impl BomPrinter<'_> {
         self.reset()?;
         writeln!(self, "{}", header)?;
         for (item, amount) in items.iter() {
-            write!(self, "{:>8} - {item}", self.amount_format.format(amount))?;
+            if item.is_fluid() {
+                let (pipe, lines) = Pipe::for_amount(amount.as_f64());
+                write!(self, "{:>8} m³/min - {} ({} x {})", self.amount_format.format_rate(amount), self.name_format.item(item), lines, pipe)?;
+            } else {
+                write!(self, "{:>8}        - {}", self.amount_format.format_rate(amount), self.name_format.item(item))?;
+            }
             writeln!(self)?;
         };
 
         Ok(())
     }
 
-    pub fn display_recipe(&mut self, recipe: &Recipe, amount: f64) -> crate::error::Result<()> {
+    pub fn display_recipe(&mut self, recipe: &Recipe, amount: Rate) -> crate::error::Result<()> {
         for (i, reactant) in recipe.inputs().iter().enumerate() {
             if i != 0 {
                 self.reset()?;
@@ -131,14 +241,17 @@ impl BomPrinter<'_> {
         Ok(())
     }
 
-    pub fn display_reactant(&mut self, reactant: &Reactant, amount: f64) -> crate::error::Result<()> {
-        let quantity = amount * (reactant.quantity() as f64);
+    pub fn display_reactant(&mut self, reactant: &Reactant, amount: Rate) -> crate::error::Result<()> {
+        let quantity = amount * reactant.quantity();
         self.fg(AMOUNT_COLOR)?;
-        write!(self, "{}", self.amount_format.format(&quantity))?;
+        write!(self, "{}", self.amount_format.format_rate(&quantity))?;
+        if reactant.item().is_fluid() {
+            write!(self, "m³")?;
+        }
         self.reset()?;
         write!(self, "*")?;
         self.fg(ITEM_COLOR)?;
-        write!(self, "{}", reactant.item())?;
+        write!(self, "{}", self.name_format.item(reactant.item()))?;
         Ok(())
     }
 }
@@ -14,15 +14,19 @@ pub trait Book {
     fn get_recipe(&self, recipe_index: usize) -> Result<&Recipe>;
 
     /// List all the items involved in all the recipes of this book
+    #[allow(dead_code)]
     fn get_involved_items(&self) -> Result<HashSet<Item>>;
 
     /// Retrieve an item based on its id
     fn get_item_by_id(&self, item_id:&str) -> Result<&Item>;
 
+    /// Retrieve a recipe based on its id, even if it is filtered out
+    fn get_recipe_by_id(&self, recipe_id:&str) -> Result<&Recipe>;
+
 }
 
 pub trait FilterableBook {
 
-    fn filter(&self, predicate:&RecipeFilter) -> Result<FilteredBook>;
+    fn filter(&self, predicate:&RecipeFilter) -> Result<FilteredBook<'_>>;
 
 }
\ No newline at end of file
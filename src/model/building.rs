@@ -24,8 +24,8 @@ pub struct Processor {
 impl Building {
     pub fn power_usage(&self) -> i32 {
         match self {
-            Building::Extractor(e) => e.power_usage as i32,
-            Building::Processor(p) => p.power_usage as i32
+            Building::Extractor(e) => e.power_usage,
+            Building::Processor(p) => p.power_usage
         }
     }
 
@@ -40,6 +40,19 @@ impl Extractor {
     pub fn is_manual(&self) -> bool {
         self.id.eq("manual")
     }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn power_usage(&self) -> i32 {
+        self.power_usage
+    }
+
+    /// The amount extracted per minute from a normal node at 100%
+    pub fn normal_extraction_rate(&self) -> u32 {
+        self.normal_extraction_rate
+    }
 }
 
 impl Processor {
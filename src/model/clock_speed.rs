@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::hash::{Hash, Hasher};
+
+use crate::error::{Error, Result};
+use crate::model::building::Building;
+use crate::model::recipe::Recipe;
+
+/// The exponent the game uses to scale the power usage of a building with its clock speed
+const POWER_EXPONENT: f64 = 1.321928;
+
+/// The clock speed of a building, in percent
+#[derive(Clone, Copy, Debug)]
+pub struct ClockSpeed(f64);
+
+impl ClockSpeed {
+    pub const DEFAULT: ClockSpeed = ClockSpeed(100f64);
+
+    pub fn new(percent: f64) -> Result<Self> {
+        if (1f64..=250f64).contains(&percent) {
+            Ok(ClockSpeed(percent))
+        } else {
+            Err(Error::InvalidClockSpeed(percent))
+        }
+    }
+
+    /// The lower clock speed of a building doing a part of its work, like one of the whole
+    /// buildings of the integer mode. It never goes above this clock speed
+    pub fn underclocked(&self, share: f64) -> ClockSpeed {
+        ClockSpeed((self.0 * share).clamp(1f64, self.0))
+    }
+
+    /// The production of a building scales linearly with its clock speed
+    pub fn factor(&self) -> f64 {
+        self.0 / 100f64
+    }
+
+    /// The power used by a building running at this clock speed. The power of
+    /// generators scales linearly like their fuel consumption
+    pub fn power_usage(&self, building: &Building) -> f64 {
+        let power_usage = building.power_usage() as f64;
+        if power_usage > 0f64 {
+            power_usage * self.factor().powf(POWER_EXPONENT)
+        } else {
+            power_usage * self.factor()
+        }
+    }
+
+    /// The number of power shards a building needs to run at this clock speed, one for each 50% above 100%
+    pub fn power_shards(&self) -> u32 {
+        if self.0 > 100f64 {
+            ((self.0 - 100f64) / 50f64).ceil() as u32
+        } else {
+            0
+        }
+    }
+}
+
+impl PartialEq for ClockSpeed {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.to_bits() == other.0.to_bits()
+    }
+}
+
+impl Eq for ClockSpeed {}
+
+impl Hash for ClockSpeed {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.to_bits().hash(state)
+    }
+}
+
+impl Display for ClockSpeed {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}%", (self.0 * 100f64).round() / 100f64)
+    }
+}
+
+/// The clock speeds of the buildings, by recipe id
+#[derive(Clone, Debug, Default)]
+pub struct ClockSpeeds {
+    default: Option<ClockSpeed>,
+    by_recipe: HashMap<String, ClockSpeed>,
+}
+
+impl ClockSpeeds {
+    pub fn new(default: Option<ClockSpeed>, by_recipe: HashMap<String, ClockSpeed>) -> Self {
+        ClockSpeeds { default, by_recipe }
+    }
+
+    pub fn of(&self, recipe: &Recipe) -> ClockSpeed {
+        self.by_recipe.get(recipe.id())
+            .or(self.default.as_ref())
+            .cloned()
+            .unwrap_or(ClockSpeed::DEFAULT)
+    }
+
+    /// The number of buildings needed to execute the recipe `amount` times per minute
+    pub fn building_count(&self, recipe: &Recipe, amount: f64) -> f64 {
+        amount / (recipe.nb_per_minute() * self.of(recipe).factor())
+    }
+
+    /// The power in MW used to execute the recipe once per minute
+    pub fn recipe_power(&self, recipe: &Recipe) -> f64 {
+        self.of(recipe).power_usage(recipe.building()) * self.building_count(recipe, 1f64)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::model::building::{Building, Processor};
+    use crate::model::clock_speed::ClockSpeed;
+
+    #[test]
+    fn clock_speed_effects() {
+        let constructor = Building::Processor(Processor::new("constructor".to_string(), "builder".to_string(), 4));
+        let generator = Building::Processor(Processor::new("coal_generator".to_string(), "builder".to_string(), -75));
+
+        let overclocked = ClockSpeed::new(250f64).unwrap();
+        assert!((overclocked.power_usage(&constructor) - 4f64 * 2.5f64.powf(1.321928)).abs() < 1e-9);
+        assert!((overclocked.power_usage(&generator) + 187.5f64).abs() < 1e-9);
+        assert_eq!(overclocked.power_shards(), 3);
+        assert_eq!(ClockSpeed::new(150f64).unwrap().power_shards(), 1);
+        assert_eq!(ClockSpeed::new(50f64).unwrap().power_shards(), 0);
+        assert!(ClockSpeed::new(0.5f64).is_err());
+        assert!(ClockSpeed::new(251f64).is_err());
+    }
+}
@@ -1,25 +1,68 @@
-use serde::Deserialize;
-use serde_json::Error;
+use std::fs::read_to_string;
+use serde::{Deserialize, Serialize};
 use crate::{FullBook, Recipe};
+use crate::error::Error::{BookDeserialization, UnknownBook};
+use crate::model::dto::registry::{DEFAULT_BOOK, find_embedded_book};
 use crate::model::dto::building::BuildingDto;
 use crate::model::dto::factory::Factory;
 use crate::model::dto::item::ItemDto;
 use crate::model::dto::recipe::RecipeDto;
+use crate::model::dto::overlay::{OverlayOrigins, RemovalsDto};
+use crate::model::dto::xml_book::parse_xml;
 
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug)]
 #[allow(dead_code)]
 pub struct BookDto {
     pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(default)]
     pub buildings: Vec<BuildingDto>,
+    #[serde(default)]
     pub items: Vec<ItemDto>,
+    #[serde(default)]
     pub recipes: Vec<RecipeDto>,
+    //only used by overlays, see BookDto::apply_overlay
+    #[serde(default, skip_serializing_if = "RemovalsDto::is_empty")]
+    pub remove: RemovalsDto,
+    #[serde(skip)]
+    pub overlay_origins: OverlayOrigins,
 }
 
 impl BookDto {
-    pub(crate) fn parse() -> Result<BookDto, Error> {
-        let book = include_str!("book_update5.json");
-        serde_json::from_str(book)
+    /// Parse the default book embedded in the executable
+    pub(crate) fn parse() -> crate::error::Result<BookDto> {
+        BookDto::embedded(DEFAULT_BOOK.version)
+    }
+
+    /// Parse the embedded book with the given version or name
+    pub(crate) fn embedded(version: &str) -> crate::error::Result<BookDto> {
+        let book = find_embedded_book(version).ok_or_else(|| UnknownBook(version.to_string()))?;
+        BookDto::parse_str(book.content, book.version)
+    }
+
+    /// Parse a book from a file, either in JSON or in XML (see recipes/book.xsd)
+    pub(crate) fn from_file(path: &str) -> crate::error::Result<BookDto> {
+        let book = read_to_string(path)?;
+        if path.ends_with(".xml") {
+            parse_xml(&book, path)
+        } else {
+            BookDto::parse_str(&book, path)
+        }
+    }
+
+    /// Parse a book from a file or, if no such file exists, the embedded book with the given version or name
+    pub(crate) fn from_file_or_embedded(path_or_version: &str) -> crate::error::Result<BookDto> {
+        if std::path::Path::new(path_or_version).exists() {
+            BookDto::from_file(path_or_version)
+        } else {
+            BookDto::embedded(path_or_version)
+        }
+    }
+
+    fn parse_str(content: &str, source: &str) -> crate::error::Result<BookDto> {
+        serde_json::from_str(content).map_err(|e| BookDeserialization(source.to_string(), e))
     }
 }
 
@@ -31,13 +74,15 @@ impl BookDto {
             .map(|r| factory.convert_recipe(r))
             .collect();
 
-        Ok(FullBook::new(factory.into_items(), recipes?))
+        Ok(FullBook::new(self.name.clone(), self.version.clone(), factory.into_items(), recipes?, self.overlay_origins.clone()))
     }
 }
 
 
 #[cfg(test)]
 mod tests {
+    use crate::error::Error;
+    use crate::model::book::Book;
     use crate::model::dto::book::BookDto;
     use crate::model::dto::factory::Factory;
     use crate::model::recipe_complexity::compute_complexity;
@@ -59,8 +104,8 @@ mod tests {
             .map(|r| factory.convert_recipe(r))
             .collect();
 
-        let mut recipes:Vec<Recipe> = recipes.unwrap()
-            .into_iter().filter(|r| is_allowed(r))
+        let recipes:Vec<Recipe> = recipes.unwrap()
+            .into_iter().filter(is_allowed)
             .collect();
 
 
@@ -71,11 +116,26 @@ mod tests {
 
     }
 
+    #[test]
+    fn find_item_by_display_name() {
+        let book = BookDto::parse().unwrap().to_full_book().unwrap();
+
+        assert_eq!(book.get_item_by_id("Reinforced Iron Plate").unwrap().id(), "reinforced_iron_plate");
+        assert_eq!(book.get_item_by_id("reinforced iron plate").unwrap().id(), "reinforced_iron_plate");
+        assert_eq!(book.get_item_by_id("iron_plate").unwrap().id(), "iron_plate");
+        assert!(book.get_item_by_id("Reinforced Plate").is_err());
+
+        //a name shared by several items is not resolved to any of them
+        let book: BookDto = serde_json::from_str(r#"{"name": "names", "buildings": [], "recipes": [], "items": [
+            {"id": "plate_a", "display-name": "Plate"}, {"id": "plate_b", "display-name": "plate"}, {"id": "rod", "display-name": "Rod"}
+        ]}"#).unwrap();
+        let book = book.to_full_book().unwrap();
+        assert_eq!(book.get_item_by_id("Rod").unwrap().id(), "rod");
+        assert!(matches!(book.get_item_by_id("Plate"), Err(Error::AmbiguousItem(_, ids)) if ids == ["plate_a", "plate_b"]));
+    }
+
     fn is_allowed(recipe: &Recipe) -> bool {
-        match recipe.id() {
-            "_iron_ingot" | "_iron_plate" | "_iron_rod" | "_screw" => true,
-            _ => false
-        }
+        matches!(recipe.id(), "_iron_ingot" | "_iron_plate" | "_iron_rod" | "_screw")
     }
 }
 
@@ -1,7 +1,7 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use crate::model::building::{Building, Extractor, Processor};
 
-#[derive(Deserialize,Debug)]
+#[derive(Deserialize, Serialize, Debug)]
 #[serde(untagged)]
 pub enum BuildingDto {
     Extractor(ExtractorDto),
@@ -10,25 +10,25 @@ pub enum BuildingDto {
 
 
 
-#[derive(Deserialize,Debug)]
+#[derive(Deserialize, Serialize, Debug)]
 pub struct ExtractorDto {
-    #[serde(rename(deserialize = "id"))]
+    #[serde(rename = "id")]
     pub id:String,
-    #[serde(rename(deserialize = "type"))]
+    #[serde(rename = "type")]
     pub kind:String,
-    #[serde(rename(deserialize = "power-usage"))]
+    #[serde(rename = "power-usage")]
     pub power_usage:i32,
-    #[serde(rename(deserialize = "normal-extraction-rate"))]
+    #[serde(rename = "normal-extraction-rate")]
     pub normal_extraction_rate:u32
 }
 
-#[derive(Deserialize,Debug)]
+#[derive(Deserialize, Serialize, Debug)]
 pub struct ProcessorDto {
-    #[serde(rename(deserialize = "id"))]
+    #[serde(rename = "id")]
     pub id:String,
-    #[serde(rename(deserialize = "type"))]
+    #[serde(rename = "type")]
     pub kind:String,
-    #[serde(rename(deserialize = "power-usage"))]
+    #[serde(rename = "power-usage")]
     pub power_usage:i32
 
 }
@@ -45,6 +45,13 @@ impl ExtractorDto {
 }
 
 impl BuildingDto {
+    pub fn id(&self) -> &str {
+        match self {
+            BuildingDto::Extractor(e) => &e.id,
+            BuildingDto::Processor(p) => &p.id
+        }
+    }
+
     pub fn create_building(&self) -> Building {
         match self {
             BuildingDto::Extractor(e) => Building::Extractor(e.create_extractor()),
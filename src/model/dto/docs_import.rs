@@ -0,0 +1,611 @@
+use std::collections::{HashMap, HashSet};
+
+use num_integer::Integer;
+use num_rational::Ratio;
+use num_traits::{Signed, ToPrimitive, Zero};
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::error::Error::DocsImportFailed;
+use crate::error::Result;
+use crate::model::dto::book::BookDto;
+use crate::model::dto::building::{BuildingDto, ExtractorDto, ProcessorDto};
+use crate::model::dto::item::{FormDto, ItemDto, ItemMetadataDto, ProductDto, ResourceDto};
+use crate::model::dto::names::NamesDto;
+use crate::model::dto::reactant::ReactantDto;
+use crate::model::dto::recipe::RecipeDto;
+use crate::model::rate::parse_decimal;
+
+/// Fluids are expressed in liters in Docs.json and in m³ in the book
+const LITERS_PER_CUBIC_METER: i64 = 1000;
+
+#[derive(Deserialize)]
+struct NativeClassDto {
+    #[serde(rename = "NativeClass")]
+    native_class: String,
+    #[serde(rename = "Classes")]
+    classes: Vec<HashMap<String, Value>>,
+}
+
+struct Descriptor {
+    id: String,
+    display_name: String,
+    fluid: bool,
+    resource: bool,
+    metadata: ItemMetadataDto,
+    //the exact energy of the item, per m³ for the fluids
+    energy: Option<Ratio<i64>>,
+}
+
+struct Extractor {
+    building: ExtractorDto,
+    any_solid: bool,
+    allowed_resources: Vec<String>,
+}
+
+struct Generator {
+    building_id: String,
+    power_production: Ratio<i64>,
+    //liters of the supplemental resource per MJ produced
+    supplemental_ratio: Ratio<i64>,
+    fuels: Vec<Fuel>,
+}
+
+struct Fuel {
+    class_name: String,
+    supplemental: Option<String>,
+    byproduct: Option<(String, Ratio<i64>)>,
+}
+
+/// Create a book from the `Docs.json` file exported by the game.
+///
+/// The node counts of the resources are not part of Docs.json. They are
+/// taken from `nodes_from` when it contains a resource with the same id.
+pub(crate) fn import_docs(content: &[u8], name: &str, nodes_from: &BookDto) -> Result<BookDto> {
+    let content = decode(content)?;
+    let docs: Vec<NativeClassDto> = serde_json::from_str(content.trim_start_matches('\u{feff}'))
+        .map_err(|e| DocsImportFailed(format!("invalid Docs.json : {}", e)))?;
+
+    let mut importer = DocsImporter::default();
+    for native_class in &docs {
+        importer.read_native_class(native_class)?;
+    }
+    importer.create_book(name, nodes_from)
+}
+
+/// Docs.json is usually exported in UTF-16 (with a BOM) but UTF-8 is accepted too
+fn decode(content: &[u8]) -> Result<String> {
+    let utf16 = match content {
+        [0xFF, 0xFE, rest @ ..] => Some(rest.chunks(2).map(|c| u16::from_le_bytes([c[0], *c.get(1).unwrap_or(&0)])).collect::<Vec<u16>>()),
+        [0xFE, 0xFF, rest @ ..] => Some(rest.chunks(2).map(|c| u16::from_be_bytes([c[0], *c.get(1).unwrap_or(&0)])).collect::<Vec<u16>>()),
+        _ => None
+    };
+
+    match utf16 {
+        Some(units) => String::from_utf16(&units).map_err(|e| DocsImportFailed(e.to_string())),
+        None => Ok(std::str::from_utf8(content)?.to_string())
+    }
+}
+
+impl Descriptor {
+    fn form(&self) -> FormDto {
+        if self.fluid { FormDto::Fluid } else { FormDto::Solid }
+    }
+}
+
+#[derive(Default)]
+struct DocsImporter {
+    descriptors: HashMap<String, Descriptor>,
+    processors: HashMap<String, ProcessorDto>,
+    extractors: Vec<Extractor>,
+    generators: Vec<Generator>,
+    recipes: Vec<HashMap<String, Value>>,
+}
+
+impl DocsImporter {
+    fn read_native_class(&mut self, native_class: &NativeClassDto) -> Result<()> {
+        let kind = native_class.native_class.rsplit('.').next().unwrap_or_default().trim_end_matches('\'');
+        for class in &native_class.classes {
+            match kind {
+                "FGRecipe" => self.recipes.push(class.clone()),
+                "FGBuildableManufacturer" | "FGBuildableManufacturerVariablePower" => {
+                    let processor = ProcessorDto {
+                        id: id_from_display_name(string_value(class, "mDisplayName")?),
+                        kind: "builder".to_string(),
+                        power_usage: power_consumption(class),
+                    };
+                    self.processors.insert(string_value(class, "ClassName")?.to_string(), processor);
+                }
+                "FGBuildableResourceExtractor" | "FGBuildableWaterPump" | "FGBuildableFrackingExtractor" => {
+                    self.extractors.push(read_extractor(kind, class)?);
+                }
+                "FGBuildableGeneratorFuel" | "FGBuildableGeneratorNuclear" => {
+                    let generator = read_generator(class)?;
+                    let processor = ProcessorDto {
+                        id: generator.building_id.clone(),
+                        kind: "builder".to_string(),
+                        power_usage: -generator.power_production.round().to_integer() as i32,
+                    };
+                    self.processors.insert(string_value(class, "ClassName")?.to_string(), processor);
+                    self.generators.push(generator);
+                }
+                kind if kind.contains("Descriptor") => {
+                    let display_name = string_value(class, "mDisplayName")?;
+                    let fluid = !matches!(optional_string_value(class, "mForm"), Some("RF_SOLID") | None);
+                    let descriptor = Descriptor {
+                        id: id_from_display_name(display_name),
+                        display_name: display_name.to_string(),
+                        fluid,
+                        resource: kind == "FGResourceDescriptor",
+                        metadata: read_metadata(class, fluid),
+                        energy: read_energy(class, fluid),
+                    };
+                    self.descriptors.insert(string_value(class, "ClassName")?.to_string(), descriptor);
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn create_book(self, name: &str, nodes_from: &BookDto) -> Result<BookDto> {
+        let mut converted = vec![];
+        for recipe in &self.recipes {
+            converted.extend(self.convert_recipe(recipe)?);
+        }
+        //the generators burn each of their fuels in a power recipe without outputs
+        for generator in &self.generators {
+            for fuel in &generator.fuels {
+                converted.extend(self.power_recipe(generator, fuel)?);
+            }
+        }
+
+        let mut recipes = vec![];
+        let mut recipe_ids = HashSet::new();
+        for recipe in converted {
+            let mut id = recipe.id.clone();
+            let mut suffix = 2;
+            while recipe_ids.contains(&id) {
+                id = format!("{}_{}", recipe.id, suffix);
+                suffix += 1;
+            }
+            recipe_ids.insert(id.clone());
+            recipes.push(RecipeDto { id, ..recipe });
+        }
+
+        let produced: HashSet<&str> = recipes.iter().flat_map(|r| r.outputs.iter()).map(|r| r.item_id.as_str()).collect();
+        let mut involved: Vec<&str> = recipes.iter()
+            .flat_map(|r| r.inputs.iter().chain(r.outputs.iter()))
+            .map(|r| r.item_id.as_str())
+            .collect::<HashSet<&str>>()
+            .into_iter()
+            .collect();
+        involved.sort_unstable();
+
+        let descriptors: HashMap<&str, (&str, &Descriptor)> = self.descriptors.iter()
+            .map(|(class_name, d)| (d.id.as_str(), (class_name.as_str(), d)))
+            .collect();
+
+        let mut resources = vec![];
+        let mut products = vec![];
+        let mut uses_manual = false;
+        for item_id in involved {
+            let descriptor = descriptors.get(item_id);
+            let names = descriptor.map(|(_, d)| NamesDto::with_display_name(&d.display_name)).unwrap_or_default();
+            let form = descriptor.map(|(_, d)| d.form()).unwrap_or_default();
+            let metadata = descriptor.map(|(_, d)| d.metadata.clone()).unwrap_or_default();
+            match descriptor {
+                Some((class_name, d)) if d.resource => resources.push(ItemDto::Resource(self.create_resource(class_name, d, nodes_from)?)),
+                _ if !produced.contains(item_id) => {
+                    uses_manual = true;
+                    resources.push(ItemDto::Resource(ResourceDto { id: item_id.to_string(), form, extractor: "manual".to_string(), extractors: vec![], impure: -1, normal: -1, pure: -1, names, metadata }));
+                }
+                _ => products.push(ItemDto::Product(ProductDto { id: item_id.to_string(), form, names, metadata }))
+            }
+        }
+
+        let mut buildings: Vec<BuildingDto> = self.extractors.into_iter().map(|e| BuildingDto::Extractor(e.building)).collect();
+        if uses_manual {
+            buildings.push(BuildingDto::Extractor(ExtractorDto { id: "manual".to_string(), kind: "miner".to_string(), power_usage: 0, normal_extraction_rate: 0 }));
+        }
+        let mut processors: Vec<ProcessorDto> = self.processors.into_values().collect();
+        processors.sort_by(|p1, p2| p1.id.cmp(&p2.id));
+        buildings.extend(processors.into_iter().map(BuildingDto::Processor));
+
+        resources.extend(products);
+        Ok(BookDto {
+            name: name.to_string(),
+            version: None,
+            buildings,
+            items: resources,
+            recipes,
+            remove: Default::default(),
+            overlay_origins: Default::default(),
+        })
+    }
+
+    fn create_resource(&self, class_name: &str, descriptor: &Descriptor, nodes_from: &BookDto) -> Result<ResourceDto> {
+        let extractor = self.extractors.iter()
+            .find(|e| e.allowed_resources.iter().any(|r| r == class_name))
+            .or_else(|| self.extractors.iter().find(|e| e.any_solid && !descriptor.fluid))
+            .map(|e| e.building.id.clone())
+            .ok_or_else(|| DocsImportFailed(format!("no extractor can extract '{}'", descriptor.id)))?;
+        //every extractor able to tap the nodes, like the tiers of miners
+        let extractors = self.extractors.iter()
+            .filter(|e| e.allowed_resources.iter().any(|r| r == class_name) || (e.any_solid && !descriptor.fluid))
+            .map(|e| e.building.id.clone())
+            .collect();
+
+        let nodes = nodes_from.items.iter().find_map(|i| match i {
+            ItemDto::Resource(r) if r.id == descriptor.id => Some((r.impure, r.normal, r.pure)),
+            _ => None
+        });
+        let (impure, normal, pure) = nodes.unwrap_or((-1, -1, -1));
+
+        let names = NamesDto::with_display_name(&descriptor.display_name);
+        Ok(ResourceDto { id: descriptor.id.clone(), form: descriptor.form(), extractor, extractors, impure, normal, pure, names, metadata: descriptor.metadata.clone() })
+    }
+
+    fn convert_recipe(&self, recipe: &HashMap<String, Value>) -> Result<Option<RecipeDto>> {
+        let building = class_names(optional_string_value(recipe, "mProducedIn").unwrap_or_default())
+            .into_iter()
+            .find_map(|c| self.processors.get(&c));
+        let building = match building {
+            Some(b) => b.id.clone(),
+            None => return Ok(None)
+        };
+
+        let class_name = string_value(recipe, "ClassName")?;
+        let display_name = string_value(recipe, "mDisplayName")?;
+        let alternate = class_name.starts_with("Recipe_Alternate_") || display_name.starts_with("Alternate:");
+        let id = id_from_display_name(display_name.trim_start_matches("Alternate:"));
+
+        let duration = parse_ratio(string_value(recipe, "mManufactoringDuration")?)?;
+        let inputs = self.convert_reactants(string_value(recipe, "mIngredients")?)?;
+        let outputs = self.convert_reactants(string_value(recipe, "mProduct")?)?;
+        let (duration, inputs, outputs) = integer_recipe(class_name, duration, inputs, outputs)?;
+
+        Ok(Some(RecipeDto {
+            id,
+            duration,
+            building,
+            alternate,
+            inputs,
+            outputs,
+            names: NamesDto::with_display_name(display_name),
+        }))
+    }
+
+    /// A fuel burnt by a generator, like the `coal_power` recipe of the embedded book : one item
+    /// (or m³) of fuel lasts its energy divided by the power of the generator
+    fn power_recipe(&self, generator: &Generator, fuel: &Fuel) -> Result<Option<RecipeDto>> {
+        let descriptor = self.descriptor(&fuel.class_name)?;
+        let energy = match descriptor.energy {
+            Some(energy) => energy,
+            None => return Ok(None)
+        };
+
+        let duration = energy / generator.power_production;
+        let mut inputs = vec![(descriptor.id.clone(), Ratio::from_integer(1))];
+        if let Some(supplemental) = &fuel.supplemental {
+            let supplemental = self.descriptor(supplemental)?;
+            let amount = energy * generator.supplemental_ratio;
+            let amount = if supplemental.fluid { amount / LITERS_PER_CUBIC_METER } else { amount };
+            inputs.push((supplemental.id.clone(), amount));
+        }
+        let mut outputs = vec![];
+        if let Some((byproduct, amount)) = &fuel.byproduct {
+            outputs.push((self.descriptor(byproduct)?.id.clone(), *amount));
+        }
+        let (duration, inputs, outputs) = integer_recipe(&fuel.class_name, duration, inputs, outputs)?;
+
+        Ok(Some(RecipeDto {
+            id: format!("{}_power", descriptor.id),
+            duration,
+            building: generator.building_id.clone(),
+            alternate: false,
+            inputs,
+            outputs,
+            names: NamesDto::with_display_name(&format!("Power ({})", descriptor.display_name)),
+        }))
+    }
+
+    fn descriptor(&self, class_name: &str) -> Result<&Descriptor> {
+        self.descriptors.get(class_name)
+            .ok_or_else(|| DocsImportFailed(format!("unknown item class '{}'", class_name)))
+    }
+
+    /// Parse a list of reactants like `((ItemClass=BlueprintGeneratedClass'"/Game/.../Desc_IronIngot.Desc_IronIngot_C"',Amount=3))`
+    fn convert_reactants(&self, value: &str) -> Result<Vec<(String, Ratio<i64>)>> {
+        let mut result = vec![];
+        for part in value.split("ItemClass=").skip(1) {
+            let (class, amount) = part.split_once("Amount=")
+                .ok_or_else(|| DocsImportFailed(format!("invalid reactant '{}'", part)))?;
+            let class = class_names(class).pop()
+                .ok_or_else(|| DocsImportFailed(format!("invalid reactant '{}'", part)))?;
+            let descriptor = self.descriptor(&class)?;
+            let amount = parse_ratio(amount.trim_end_matches(|c: char| !c.is_ascii_digit()))?;
+            let amount = if descriptor.fluid { amount / LITERS_PER_CUBIC_METER } else { amount };
+            result.push((descriptor.id.clone(), amount));
+        }
+        Ok(result)
+    }
+}
+
+/// Scale a recipe so that its duration and all its quantities are integers, the way the
+/// embedded book writes the recipes whose durations or quantities are fractional
+fn integer_recipe(class_name: &str, duration: Ratio<i64>, inputs: Vec<(String, Ratio<i64>)>, outputs: Vec<(String, Ratio<i64>)>) -> Result<(u32, Vec<ReactantDto>, Vec<ReactantDto>)> {
+    let scale = inputs.iter().chain(outputs.iter()).map(|(_, q)| q)
+        .fold(*duration.denom(), |lcm, q| lcm.lcm(q.denom()));
+    let to_u32 = |value: Ratio<i64>| (value * scale).to_integer().to_u32()
+        .ok_or_else(|| DocsImportFailed(format!("invalid quantity in recipe '{}'", class_name)));
+    let convert = |reactants: Vec<(String, Ratio<i64>)>| reactants.into_iter()
+        .map(|(item_id, quantity)| to_u32(quantity).map(|quantity| ReactantDto { item_id, quantity }))
+        .collect::<Result<Vec<ReactantDto>>>();
+
+    Ok((to_u32(duration)?, convert(inputs)?, convert(outputs)?))
+}
+
+fn read_extractor(native_class: &str, class: &HashMap<String, Value>) -> Result<Extractor> {
+    let forms = optional_string_value(class, "mAllowedResourceForms").unwrap_or("RF_SOLID");
+    let solid = forms.contains("RF_SOLID");
+
+    let cycle_time = parse_ratio(optional_string_value(class, "mExtractCycleTime").unwrap_or("1"))?;
+    let items_per_cycle = parse_ratio(optional_string_value(class, "mItemsPerCycle").unwrap_or("1"))?;
+    let items_per_cycle = if solid { items_per_cycle } else { items_per_cycle / LITERS_PER_CUBIC_METER };
+    if cycle_time.is_zero() {
+        return Err(DocsImportFailed(format!("invalid extraction cycle time for '{}'", string_value(class, "ClassName")?)));
+    }
+    let normal_extraction_rate = (items_per_cycle * 60 / cycle_time).round().to_integer() as u32;
+
+    let only_certain_resources = optional_string_value(class, "mOnlyAllowCertainResources")
+        .map(|v| v.eq_ignore_ascii_case("true"))
+        .unwrap_or(false);
+
+    Ok(Extractor {
+        building: ExtractorDto {
+            id: id_from_display_name(string_value(class, "mDisplayName")?),
+            kind: extractor_kind(native_class, solid).to_string(),
+            power_usage: power_consumption(class),
+            normal_extraction_rate,
+        },
+        any_solid: solid && !only_certain_resources,
+        allowed_resources: class_names(optional_string_value(class, "mAllowedResources").unwrap_or_default()),
+    })
+}
+
+/// The fuels of a generator are listed in `mFuel`, with the supplemental resource (like the water of
+/// the coal generators) and the byproduct (like the waste of the nuclear power plants) of each
+fn read_generator(class: &HashMap<String, Value>) -> Result<Generator> {
+    let power_production = parse_ratio(string_value(class, "mPowerProduction")?)?;
+    if power_production.is_zero() {
+        return Err(DocsImportFailed(format!("invalid power production for '{}'", string_value(class, "ClassName")?)));
+    }
+    let supplemental_ratio = parse_ratio(optional_string_value(class, "mSupplementalToPowerRatio").unwrap_or("0"))?;
+    let requires_supplemental = optional_string_value(class, "mRequiresSupplementalResource")
+        .map(|v| v.eq_ignore_ascii_case("true"))
+        .unwrap_or(false);
+
+    let mut fuels = vec![];
+    for fuel in class.get("mFuel").and_then(|f| f.as_array()).into_iter().flatten() {
+        let class_name = |key: &str| fuel.get(key).and_then(|v| v.as_str())
+            .and_then(|v| class_names(v).pop().or_else(|| Some(v.to_string())))
+            .filter(|v| !v.is_empty());
+        let fuel_class = match class_name("mFuelClass") {
+            Some(c) => c,
+            None => continue
+        };
+        let byproduct_amount = fuel.get("mByproductAmount").and_then(|v| v.as_str()).and_then(parse_decimal);
+        fuels.push(Fuel {
+            class_name: fuel_class,
+            supplemental: class_name("mSupplementalResourceClass").filter(|_| requires_supplemental),
+            byproduct: class_name("mByproduct").zip(byproduct_amount).filter(|(_, a)| !a.is_zero()),
+        });
+    }
+
+    Ok(Generator {
+        building_id: id_from_display_name(string_value(class, "mDisplayName")?),
+        power_production,
+        supplemental_ratio,
+        fuels,
+    })
+}
+
+/// The type of an extractor in the XML book (see recipes/book.xsd). The oil extractors are
+/// resource extractors like the miners, but for fluids only
+fn extractor_kind(native_class: &str, solid: bool) -> &'static str {
+    match native_class {
+        "FGBuildableWaterPump" => "water_extractor",
+        "FGBuildableFrackingExtractor" => "resource_well_extractor",
+        _ if solid => "miner",
+        _ => "oil_extractor",
+    }
+}
+
+/// The values of 0 in Docs.json mean that the item has no such property
+fn read_metadata(class: &HashMap<String, Value>, fluid: bool) -> ItemMetadataDto {
+    let value = |key: &str| optional_string_value(class, key)
+        .and_then(|v| v.trim().parse::<f64>().ok())
+        .filter(|v| *v > 0f64);
+
+    let stack_size = match optional_string_value(class, "mStackSize") {
+        Some("SS_ONE") => Some(1),
+        Some("SS_SMALL") => Some(50),
+        Some("SS_MEDIUM") => Some(100),
+        Some("SS_BIG") => Some(200),
+        Some("SS_HUGE") => Some(500),
+        _ => None
+    };
+    let energy = value("mEnergyValue").map(|e| if fluid { e * LITERS_PER_CUBIC_METER as f64 } else { e });
+
+    ItemMetadataDto {
+        stack_size,
+        sink_points: value("mResourceSinkPoints").map(|p| p as u32),
+        energy,
+        radioactivity: value("mRadioactiveDecay"),
+    }
+}
+
+fn read_energy(class: &HashMap<String, Value>, fluid: bool) -> Option<Ratio<i64>> {
+    optional_string_value(class, "mEnergyValue")
+        .and_then(parse_decimal)
+        .filter(|e| e.is_positive())
+        .map(|e| if fluid { e * LITERS_PER_CUBIC_METER } else { e })
+}
+
+fn optional_string_value<'a>(class: &'a HashMap<String, Value>, key: &str) -> Option<&'a str> {
+    class.get(key).and_then(|v| v.as_str())
+}
+
+fn string_value<'a>(class: &'a HashMap<String, Value>, key: &str) -> Result<&'a str> {
+    optional_string_value(class, key).ok_or_else(|| {
+        let class_name = optional_string_value(class, "ClassName").unwrap_or("?");
+        DocsImportFailed(format!("missing '{}' in class '{}'", key, class_name))
+    })
+}
+
+/// Buildings with a variable power usage (like the particle accelerator) use the average of their estimated consumption
+fn power_consumption(class: &HashMap<String, Value>) -> i32 {
+    let value = |key: &str| optional_string_value(class, key)
+        .and_then(|v| v.parse::<f64>().ok())
+        .unwrap_or(0f64);
+
+    let power = value("mPowerConsumption");
+    let power = if power > 0f64 {
+        power
+    } else {
+        (value("mEstimatedMininumPowerConsumption") + value("mEstimatedMaximumPowerConsumption")) / 2f64
+    };
+    power.round() as i32
+}
+
+/// Extract the class names of a list of paths like `("/Game/.../Build_SmelterMk1.Build_SmelterMk1_C","/Game/...")`
+fn class_names(value: &str) -> Vec<String> {
+    value.split(['"', '\'', ',', '(', ')'])
+        .filter(|t| t.starts_with("/Game/") || t.starts_with("/Script/"))
+        .filter_map(|t| t.rsplit('.').next())
+        .map(|t| t.to_string())
+        .collect()
+}
+
+/// Convert a display name like `Miner Mk.1` or `A.I. Limiter` into an id like `miner_mk1` or `ai_limiter`
+fn id_from_display_name(display_name: &str) -> String {
+    let mut id = String::new();
+    for c in display_name.chars().filter(|c| !matches!(c, '.' | '\'')) {
+        if c.is_alphanumeric() {
+            id.extend(c.to_lowercase());
+        } else if !id.is_empty() && !id.ends_with('_') {
+            id.push('_');
+        }
+    }
+    id.trim_end_matches('_').to_string()
+}
+
+/// Parse a decimal number like `6.000000` into an exact ratio
+fn parse_ratio(value: &str) -> Result<Ratio<i64>> {
+    parse_decimal(value).ok_or_else(|| DocsImportFailed(format!("invalid number '{}'", value)))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::book_diff::{diff_books, RecipeChange};
+    use crate::model::dto::book::BookDto;
+    use crate::model::dto::building::BuildingDto;
+    use crate::model::dto::docs_import::import_docs;
+    use crate::model::dto::item::{FormDto, ItemDto};
+    use crate::model::dto::recipe::RecipeDto;
+
+    fn imported_book() -> BookDto {
+        let nodes_from = BookDto::parse().unwrap();
+        import_docs(include_bytes!("docs_fixture.json"), "fixture", &nodes_from).unwrap()
+    }
+
+    fn recipe<'a>(book: &'a BookDto, id: &str) -> &'a RecipeDto {
+        book.recipes.iter().find(|r| r.id == id).unwrap()
+    }
+
+    #[test]
+    fn import_recipes() {
+        let book = imported_book();
+
+        let recipe_ids: Vec<&str> = book.recipes.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(recipe_ids, vec!["iron_ingot", "iron_plate", "pure_iron_ingot", "biomass_leaves", "coal_power", "compacted_coal_power"]);
+
+        let iron_plate = recipe(&book, "iron_plate");
+        assert_eq!(iron_plate.building, "constructor");
+        assert_eq!(iron_plate.duration, 6);
+        assert!(!iron_plate.alternate);
+
+        let pure_iron_ingot = recipe(&book, "pure_iron_ingot");
+        assert!(pure_iron_ingot.alternate);
+        assert_eq!(pure_iron_ingot.building, "refinery");
+        let water = pure_iron_ingot.inputs.iter().find(|r| r.item_id == "water").unwrap();
+        assert_eq!(water.quantity, 4);
+
+        //75 MW burn a coal of 300 MJ in 4 seconds, with 10 liters of water per MJ
+        let coal_power = recipe(&book, "coal_power");
+        assert_eq!(coal_power.building, "coal_generator");
+        assert_eq!(coal_power.duration, 4);
+        assert_eq!(coal_power.inputs.iter().map(|r| (r.item_id.as_str(), r.quantity)).collect::<Vec<_>>(), vec![("coal", 1), ("water", 3)]);
+        assert!(coal_power.outputs.is_empty());
+
+        //the 8.4 seconds of a compacted coal are scaled to be an integer
+        let compacted_coal_power = recipe(&book, "compacted_coal_power");
+        assert_eq!(compacted_coal_power.duration, 84);
+        assert_eq!(compacted_coal_power.inputs.iter().map(|r| r.quantity).collect::<Vec<_>>(), vec![10, 63]);
+    }
+
+    #[test]
+    fn same_units_as_the_embedded_book() {
+        let embedded = BookDto::parse().unwrap();
+        let diff = diff_books(&embedded, &imported_book());
+
+        //the recipes shared with the embedded book, the power ones included, have the same durations and quantities
+        let changes: Vec<&RecipeChange> = diff.changed_recipes.iter()
+            .flat_map(|c| c.changes.iter())
+            .filter(|c| matches!(c, RecipeChange::Duration { .. } | RecipeChange::Input { .. } | RecipeChange::Output { .. }))
+            .collect();
+        assert!(changes.is_empty(), "{:?}", changes);
+        assert!(!diff.added_recipes.contains(&"compacted_coal_power".to_string()));
+        assert!(!diff.added_buildings.contains(&"coal_generator".to_string()));
+    }
+
+    #[test]
+    fn import_items_and_buildings() {
+        let book = imported_book();
+
+        let resource = |id: &str| book.items.iter().find_map(|i| match i {
+            ItemDto::Resource(r) if r.id == id => Some(r),
+            _ => None
+        }).unwrap();
+
+        let iron_ore = resource("iron_ore");
+        assert_eq!(iron_ore.extractor, "miner_mk1");
+        assert_eq!(iron_ore.extractors, vec!["miner_mk1", "miner_mk2"]);
+        assert_eq!(resource("water").extractors, vec!["water_extractor"]);
+        assert_eq!((iron_ore.impure, iron_ore.normal, iron_ore.pure), (33, 41, 46));
+        assert_eq!(resource("water").extractor, "water_extractor");
+        assert_eq!(resource("water").form, FormDto::Fluid);
+        assert_eq!(iron_ore.form, FormDto::Solid);
+        assert_eq!(iron_ore.metadata.stack_size, Some(100));
+        assert_eq!(iron_ore.metadata.sink_points, Some(1));
+        assert_eq!(resource("leaves").metadata.energy, Some(15f64));
+        assert_eq!(resource("water").metadata.stack_size, None);
+        assert_eq!(resource("leaves").extractor, "manual");
+
+        let water_extractor = book.buildings.iter().find_map(|b| match b {
+            BuildingDto::Extractor(e) if e.id == "water_extractor" => Some(e),
+            _ => None
+        }).unwrap();
+        assert_eq!(water_extractor.normal_extraction_rate, 120);
+        assert_eq!(water_extractor.kind, "water_extractor");
+        let miner = book.buildings.iter().find_map(|b| match b {
+            BuildingDto::Extractor(e) if e.id == "miner_mk1" => Some(e),
+            _ => None
+        }).unwrap();
+        assert_eq!(miner.kind, "miner");
+
+        assert!(book.to_full_book().is_ok());
+    }
+}
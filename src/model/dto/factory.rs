@@ -53,7 +53,9 @@ impl Factory {
         let inputs: crate::error::Result<Vec<Reactant>> = recipe.inputs.iter().map(|r| self.convert_reactant(r)).collect();
         let outputs: crate::error::Result<Vec<Reactant>> = recipe.outputs.iter().map(|r| self.convert_reactant(r)).collect();
 
-        Ok(Recipe::new(id, duration, building, alternate, inputs?, outputs?))
+        let names = recipe.names.create_names();
+
+        Ok(Recipe::new(id, duration, building, alternate, inputs?, outputs?, names))
     }
 
     pub fn convert_reactant(&self, reactant: &ReactantDto) -> Result<Reactant> {
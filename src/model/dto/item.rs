@@ -1,35 +1,102 @@
 use std::collections::HashMap;
-use serde::Deserialize;
-use crate::model::building::Building;
-use crate::model::item::{Item, Nodes, Product, Resource};
+use serde::{Deserialize, Serialize};
+use crate::model::building::{Building, Extractor};
+use crate::model::dto::names::NamesDto;
+use crate::model::item::{Form, Item, ItemMetadata, Nodes, Product, Resource};
 use crate::error::{Error,Result};
 
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug)]
 #[serde(untagged)]
 pub enum ItemDto {
     Resource(ResourceDto),
     Product(ProductDto),
 }
 
-#[derive(Deserialize, Debug)]
+/// The state of an item, items are solid unless stated otherwise
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Eq, PartialEq, Default)]
+pub enum FormDto {
+    #[default]
+    #[serde(rename = "solid")]
+    Solid,
+    #[serde(rename = "fluid")]
+    Fluid,
+}
+
+impl FormDto {
+    pub fn is_solid(&self) -> bool {
+        *self == FormDto::Solid
+    }
+
+    fn create_form(&self) -> Form {
+        match self {
+            FormDto::Solid => Form::Solid,
+            FormDto::Fluid => Form::Fluid,
+        }
+    }
+}
+
+/// Optional data about an item, used for planning but not needed to compute a BoM
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+pub struct ItemMetadataDto {
+    #[serde(rename = "stack-size", default, skip_serializing_if = "Option::is_none")]
+    pub stack_size: Option<u32>,
+    //the number of AWESOME Sink points given for one item
+    #[serde(rename = "sink-points", default, skip_serializing_if = "Option::is_none")]
+    pub sink_points: Option<u32>,
+    //the energy in MJ released when burnt in a generator (per m³ for fluids)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub energy: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub radioactivity: Option<f64>,
+}
+
+impl ItemMetadataDto {
+    fn create_metadata(&self) -> ItemMetadata {
+        ItemMetadata::new(self.stack_size, self.sink_points, self.energy, self.radioactivity)
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
 pub struct ProductDto {
-    id: String,
+    pub id: String,
+    #[serde(default, skip_serializing_if = "FormDto::is_solid")]
+    pub form: FormDto,
+    #[serde(flatten)]
+    pub names: NamesDto,
+    #[serde(flatten)]
+    pub metadata: ItemMetadataDto,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug)]
 #[allow(dead_code)]
 pub struct ResourceDto {
-    id: String,
-    extractor: String,
-    impure: i32,
-    normal: i32,
-    pure: i32,
+    pub id: String,
+    #[serde(default, skip_serializing_if = "FormDto::is_solid")]
+    pub form: FormDto,
+    pub extractor: String,
+    //the extractors able to tap the nodes, like the tiers of miners (the extractor alone if empty)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extractors: Vec<String>,
+    pub impure: i32,
+    pub normal: i32,
+    pub pure: i32,
+    #[serde(flatten)]
+    pub names: NamesDto,
+    #[serde(flatten)]
+    pub metadata: ItemMetadataDto,
 }
 
 
 impl ItemDto {
 
+    pub fn id(&self) -> &str {
+        match self {
+            ItemDto::Resource(r) => &r.id,
+            ItemDto::Product(p) => &p.id
+        }
+    }
+
     pub fn create_item(&self, buildings:&HashMap<String,Building>) -> Result<Item> {
         match self {
             ItemDto::Resource(r) => r.create_resource(buildings).map(Item::Resource),
@@ -53,10 +120,25 @@ impl ResourceDto {
 
         let nodes = if total == 0 {None} else {Some(Nodes::new(impure,normal,pure))};
 
-        match extractor {
-            Building::Extractor(e) => Ok(Resource::new(self.id.clone(), e.clone(), nodes)),
-            Building::Processor(_) => Err(Error::InvalidBuilding(self.extractor.to_string()))
-        }
+        let extractor = match extractor {
+            Building::Extractor(e) => e,
+            Building::Processor(_) => return Err(Error::InvalidBuilding(self.extractor.to_string()))
+        };
+
+        let mut extractors = if self.extractors.is_empty() {
+            vec![extractor.clone()]
+        } else {
+            self.extractors.iter()
+                .map(|id| match buildings.get(id) {
+                    Some(Building::Extractor(e)) => Ok(e.clone()),
+                    Some(Building::Processor(_)) => Err(Error::InvalidBuilding(id.to_string())),
+                    None => Err(Error::UnknownBuilding(id.to_string())),
+                })
+                .collect::<Result<Vec<Extractor>>>()?
+        };
+        extractors.sort_by_key(|e| e.normal_extraction_rate());
+
+        Ok(Resource::new(self.id.clone(), self.form.create_form(), extractors, nodes, self.names.create_names(), self.metadata.create_metadata()))
     }
 }
 
@@ -70,6 +152,6 @@ fn to_u32(value:i32) -> u32 {
 
 impl ProductDto {
     fn create_product(&self) -> Result<Product> {
-        Ok(Product::new(self.id.clone()))
+        Ok(Product::new(self.id.clone(), self.form.create_form(), self.names.create_names(), self.metadata.create_metadata()))
     }
 }
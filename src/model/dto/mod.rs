@@ -4,3 +4,8 @@ pub(crate) mod recipe;
 pub(crate) mod reactant;
 pub(crate) mod building;
 pub(crate) mod factory;
+pub(crate) mod xml_book;
+pub(crate) mod docs_import;
+pub(crate) mod registry;
+pub(crate) mod overlay;
+pub(crate) mod names;
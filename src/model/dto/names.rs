@@ -0,0 +1,25 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::names::Names;
+
+/// The optional human-readable names of an item or a recipe
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+pub struct NamesDto {
+    #[serde(rename = "display-name", default, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    //localized names, by locale (like 'fr' or 'pt_BR')
+    #[serde(rename = "localized-names", default, skip_serializing_if = "HashMap::is_empty")]
+    pub localized_names: HashMap<String, String>,
+}
+
+impl NamesDto {
+    pub fn with_display_name(display_name: &str) -> Self {
+        NamesDto { display_name: Some(display_name.to_string()), localized_names: HashMap::new() }
+    }
+
+    pub fn create_names(&self) -> Names {
+        Names::new(self.display_name.clone(), self.localized_names.clone())
+    }
+}
@@ -0,0 +1,106 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::dto::book::BookDto;
+
+/// The ids of the entries an overlay removes from the book it is applied to
+#[derive(Deserialize, Serialize, Debug, Default)]
+pub struct RemovalsDto {
+    #[serde(default)]
+    pub buildings: Vec<String>,
+    #[serde(default)]
+    pub items: Vec<String>,
+    #[serde(default)]
+    pub recipes: Vec<String>,
+}
+
+impl RemovalsDto {
+    pub fn is_empty(&self) -> bool {
+        self.buildings.is_empty() && self.items.is_empty() && self.recipes.is_empty()
+    }
+}
+
+/// The name of the overlay that added or replaced an entry, by entry id
+#[derive(Debug, Default, Clone)]
+pub struct OverlayOrigins {
+    pub buildings: HashMap<String, String>,
+    pub items: HashMap<String, String>,
+    pub recipes: HashMap<String, String>,
+}
+
+impl BookDto {
+    /// Apply an overlay to this book. The entries listed in the `remove` section of the overlay
+    /// are removed first, then the entries of the overlay replace the ones with the same id
+    /// or are added to the book.
+    pub(crate) fn apply_overlay(&mut self, overlay: BookDto) {
+        let removals = &overlay.remove;
+        self.buildings.retain(|b| !removals.buildings.iter().any(|id| id == b.id()));
+        self.items.retain(|i| !removals.items.iter().any(|id| id == i.id()));
+        self.recipes.retain(|r| !removals.recipes.contains(&r.id));
+        for id in &removals.buildings {
+            self.overlay_origins.buildings.remove(id);
+        }
+        for id in &removals.items {
+            self.overlay_origins.items.remove(id);
+        }
+        for id in &removals.recipes {
+            self.overlay_origins.recipes.remove(id);
+        }
+
+        for building in overlay.buildings {
+            self.overlay_origins.buildings.insert(building.id().to_string(), overlay.name.clone());
+            merge(&mut self.buildings, building, |b| b.id().to_string());
+        }
+        for item in overlay.items {
+            self.overlay_origins.items.insert(item.id().to_string(), overlay.name.clone());
+            merge(&mut self.items, item, |i| i.id().to_string());
+        }
+        for recipe in overlay.recipes {
+            self.overlay_origins.recipes.insert(recipe.id.clone(), overlay.name.clone());
+            merge(&mut self.recipes, recipe, |r| r.id.clone());
+        }
+    }
+}
+
+fn merge<T>(entries: &mut Vec<T>, entry: T, id: impl Fn(&T) -> String) {
+    let entry_id = id(&entry);
+    match entries.iter_mut().find(|e| id(e) == entry_id) {
+        Some(existing) => *existing = entry,
+        None => entries.push(entry)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::model::dto::book::BookDto;
+
+    #[test]
+    fn apply_overlay() {
+        let mut book = BookDto::parse().unwrap();
+        let overlay: BookDto = serde_json::from_str(r#"{
+            "name": "mods",
+            "remove": {"recipes": ["iron_rod"]},
+            "items": [{"id": "iron_gear"}],
+            "recipes": [
+                {"id": "iron_plate", "building": "constructor", "alternate": false, "duration": 4,
+                 "inputs": [{"item": "iron_ingot", "quantity": 2}], "outputs": [{"item": "iron_plate", "quantity": 2}]},
+                {"id": "iron_gear", "building": "constructor", "alternate": false, "duration": 2,
+                 "inputs": [{"item": "iron_plate", "quantity": 1}], "outputs": [{"item": "iron_gear", "quantity": 1}]}
+            ]
+        }"#).unwrap();
+        let nb_recipes = book.recipes.len();
+
+        book.apply_overlay(overlay);
+
+        assert_eq!(book.recipes.len(), nb_recipes);
+        assert!(!book.recipes.iter().any(|r| r.id == "iron_rod"));
+        assert_eq!(book.recipes.iter().find(|r| r.id == "iron_plate").unwrap().duration, 4);
+
+        let full_book = book.to_full_book().unwrap();
+        assert_eq!(full_book.recipe_overlay("iron_gear"), Some("mods"));
+        assert_eq!(full_book.item_overlay("iron_gear"), Some("mods"));
+        assert_eq!(full_book.recipe_overlay("screw"), None);
+    }
+}
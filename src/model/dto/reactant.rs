@@ -1,10 +1,10 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Deserialize,Debug)]
+#[derive(Deserialize, Serialize, Debug)]
 pub struct ReactantDto {
-    #[serde(rename(deserialize = "item"))]
+    #[serde(rename = "item")]
     pub item_id:String,
-    #[serde(rename(deserialize = "quantity"))]
+    #[serde(rename = "quantity")]
     pub quantity:u32,
 }
 
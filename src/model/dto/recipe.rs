@@ -1,7 +1,8 @@
+use crate::model::dto::names::NamesDto;
 use crate::model::dto::reactant::ReactantDto;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug)]
 #[allow(dead_code)]
 pub struct RecipeDto {
     pub id: String,
@@ -10,6 +11,8 @@ pub struct RecipeDto {
     pub alternate: bool,
     pub inputs: Vec<ReactantDto>,
     pub outputs: Vec<ReactantDto>,
+    #[serde(flatten)]
+    pub names: NamesDto,
 }
 
 
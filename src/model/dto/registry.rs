@@ -0,0 +1,43 @@
+/// A book embedded in the executable
+pub struct EmbeddedBook {
+    pub name: &'static str,
+    pub version: &'static str,
+    pub content: &'static str,
+}
+
+/// The books embedded in the executable, the last one being the default. Only the book of
+/// update 5 is bundled for now, the books of other game versions are to be added here
+pub const EMBEDDED_BOOKS: [EmbeddedBook; 1] = [
+    EmbeddedBook { name: "EarlyAccess", version: "update5", content: include_str!("book_update5.json") },
+];
+
+pub const DEFAULT_BOOK: &EmbeddedBook = &EMBEDDED_BOOKS[EMBEDDED_BOOKS.len() - 1];
+
+/// Find an embedded book by its version, or by its name if it is not ambiguous
+pub fn find_embedded_book(version_or_name: &str) -> Option<&'static EmbeddedBook> {
+    EMBEDDED_BOOKS.iter()
+        .find(|b| b.version.eq_ignore_ascii_case(version_or_name))
+        .or_else(|| {
+            let mut books = EMBEDDED_BOOKS.iter().filter(|b| b.name.eq_ignore_ascii_case(version_or_name));
+            match (books.next(), books.next()) {
+                (Some(book), None) => Some(book),
+                _ => None
+            }
+        })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::model::dto::book::BookDto;
+    use crate::model::dto::registry::EMBEDDED_BOOKS;
+
+    #[test]
+    fn embedded_books_match_their_content() {
+        for book in &EMBEDDED_BOOKS {
+            let dto = BookDto::embedded(book.version).unwrap();
+            assert_eq!(dto.name, book.name);
+            assert_eq!(dto.version.as_deref(), Some(book.version));
+        }
+    }
+}
@@ -0,0 +1,407 @@
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use xml::attribute::OwnedAttribute;
+use xml::common::Position;
+use xml::reader::{EventReader, XmlEvent};
+
+use crate::error::Error::XmlBookDeserialization;
+use crate::model::dto::book::BookDto;
+use crate::model::dto::building::{BuildingDto, ExtractorDto, ProcessorDto};
+use crate::model::dto::item::{FormDto, ItemDto, ItemMetadataDto, ProductDto, ResourceDto};
+use crate::model::dto::names::NamesDto;
+use crate::model::dto::reactant::ReactantDto;
+use crate::model::dto::recipe::RecipeDto;
+
+/// The namespace of the elements defined by recipes/book.xsd
+const BOOK_NAMESPACE: &str = "pero:book";
+
+const BUILDING_TYPES: [&str; 5] = ["builder", "miner", "oil_extractor", "water_extractor", "resource_well_extractor"];
+const RESOURCE_EXTRACTORS: [&str; 5] = ["miner", "oil_extractor", "water_extractor", "manual", "resource_well_extractor"];
+
+type XmlResult<T> = std::result::Result<T, String>;
+
+/// Parse a book written in the XML format described by recipes/book.xsd
+pub(crate) fn parse_xml(content: &str, source: &str) -> crate::error::Result<BookDto> {
+    read_element_tree(content)
+        .and_then(|root| XmlBookReader::default().read_book(&root))
+        .map_err(|e| XmlBookDeserialization(source.to_string(), e))
+}
+
+
+struct Element {
+    name: String,
+    line: u64,
+    attributes: Vec<OwnedAttribute>,
+    children: Vec<Element>,
+}
+
+fn read_element_tree(content: &str) -> XmlResult<Element> {
+    let mut reader = EventReader::from_str(content);
+    let mut stack: Vec<Element> = vec![];
+
+    loop {
+        let event = reader.next().map_err(|e| e.to_string())?;
+        let line = reader.position().row + 1;
+        match event {
+            XmlEvent::StartElement { name, attributes, .. } => {
+                if name.namespace_ref() != Some(BOOK_NAMESPACE) {
+                    return Err(format!("line {} : element <{}> is not in the '{}' namespace", line, name.local_name, BOOK_NAMESPACE));
+                }
+                stack.push(Element { name: name.local_name, line, attributes, children: vec![] });
+            }
+            XmlEvent::EndElement { .. } => {
+                let element = stack.pop().ok_or_else(|| format!("line {} : unexpected closing element", line))?;
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(element),
+                    None => return Ok(element)
+                }
+            }
+            XmlEvent::Characters(text) | XmlEvent::CData(text) => {
+                return Err(format!("line {} : unexpected text '{}'", line, text.trim()));
+            }
+            XmlEvent::EndDocument => return Err("the document has no root element".to_string()),
+            _ => {}
+        }
+    }
+}
+
+impl Element {
+    fn expect_name(&self, name: &str) -> XmlResult<()> {
+        if self.name == name {
+            Ok(())
+        } else {
+            Err(format!("line {} : expected <{}> but found <{}>", self.line, name, self.name))
+        }
+    }
+
+    fn check_attributes(&self, allowed: &[&str]) -> XmlResult<()> {
+        match self.attributes.iter().find(|a| !allowed.contains(&a.name.local_name.as_str())) {
+            Some(a) => Err(format!("line {} : attribute '{}' is not allowed on <{}>", self.line, a.name.local_name, self.name)),
+            None => Ok(())
+        }
+    }
+
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes.iter()
+            .find(|a| a.name.local_name == name)
+            .map(|a| a.value.as_str())
+    }
+
+    fn required_attribute(&self, name: &str) -> XmlResult<&str> {
+        self.attribute(name)
+            .ok_or_else(|| format!("line {} : missing required attribute '{}' on <{}>", self.line, name, self.name))
+    }
+
+    fn int_attribute(&self, name: &str, default: Option<i32>) -> XmlResult<i32> {
+        match (self.attribute(name), default) {
+            (Some(value), _) => value.trim().parse::<i32>()
+                .map_err(|_| format!("line {} : attribute '{}' of <{}> is not an integer : '{}'", self.line, name, self.name, value)),
+            (None, Some(default)) => Ok(default),
+            (None, None) => self.required_attribute(name).map(|_| 0)
+        }
+    }
+
+    fn positive_attribute(&self, name: &str) -> XmlResult<u32> {
+        let value = self.int_attribute(name, None)?;
+        if value < 0 {
+            Err(format!("line {} : attribute '{}' of <{}> must not be negative", self.line, name, self.name))
+        } else {
+            Ok(value as u32)
+        }
+    }
+
+    fn optional_number_attribute<T: FromStr>(&self, name: &str) -> XmlResult<Option<T>> {
+        self.attribute(name)
+            .map(|value| value.trim().parse::<T>()
+                .map_err(|_| format!("line {} : attribute '{}' of <{}> is not a valid number : '{}'", self.line, name, self.name, value)))
+            .transpose()
+    }
+
+    fn bool_attribute(&self, name: &str) -> XmlResult<bool> {
+        match self.required_attribute(name)?.trim() {
+            "true" | "1" => Ok(true),
+            "false" | "0" => Ok(false),
+            value => Err(format!("line {} : attribute '{}' of <{}> is not a boolean : '{}'", self.line, name, self.name, value))
+        }
+    }
+
+    fn enum_attribute(&self, name: &str, values: &[&str]) -> XmlResult<String> {
+        let value = self.required_attribute(name)?;
+        if values.contains(&value) {
+            Ok(value.to_string())
+        } else {
+            Err(format!("line {} : invalid value '{}' for attribute '{}' of <{}>, expected one of {}", self.line, value, name, self.name, values.join(", ")))
+        }
+    }
+
+    fn form(&self) -> XmlResult<FormDto> {
+        match self.attribute("form") {
+            None | Some("solid") => Ok(FormDto::Solid),
+            Some("fluid") => Ok(FormDto::Fluid),
+            Some(value) => Err(format!("line {} : invalid value '{}' for attribute 'form' of <{}>, expected one of solid, fluid", self.line, value, self.name))
+        }
+    }
+
+    fn metadata(&self) -> XmlResult<ItemMetadataDto> {
+        Ok(ItemMetadataDto {
+            stack_size: self.optional_number_attribute("stack-size")?,
+            sink_points: self.optional_number_attribute("sink-points")?,
+            energy: self.optional_number_attribute("energy")?,
+            radioactivity: self.optional_number_attribute("radioactivity")?,
+        })
+    }
+
+    fn names(&self) -> NamesDto {
+        self.attribute("display-name").map(NamesDto::with_display_name).unwrap_or_default()
+    }
+
+    fn non_empty_children(&self) -> XmlResult<&[Element]> {
+        if self.children.is_empty() {
+            Err(format!("line {} : <{}> must not be empty", self.line, self.name))
+        } else {
+            Ok(&self.children)
+        }
+    }
+}
+
+
+/// Keep track of the ids of the document since they must be unique (xsd:ID)
+#[derive(Default)]
+struct XmlBookReader {
+    ids: HashSet<String>,
+}
+
+impl XmlBookReader {
+    fn register_id(&mut self, element: &Element) -> XmlResult<String> {
+        let id = element.required_attribute("id")?;
+        if self.ids.insert(id.to_string()) {
+            Ok(id.to_string())
+        } else {
+            Err(format!("line {} : duplicate id '{}'", element.line, id))
+        }
+    }
+
+    fn read_book(&mut self, book: &Element) -> XmlResult<BookDto> {
+        book.expect_name("book")?;
+        book.check_attributes(&["name", "version"])?;
+        let name = book.required_attribute("name")?.to_string();
+        let version = book.attribute("version").map(|v| v.to_string());
+
+        let (buildings, items, recipes) = match book.children.as_slice() {
+            [buildings, items, recipes] => (buildings, items, recipes),
+            _ => return Err(format!("line {} : <book> must contain exactly <buildings>, <items> and <recipes>", book.line))
+        };
+
+        let mut buildings = self.read_buildings(buildings)?;
+        let items = self.read_items(items, &mut buildings)?;
+        let recipes = self.read_recipes(recipes, &buildings, &items)?;
+
+        Ok(BookDto { name, version, buildings, items, recipes, remove: Default::default(), overlay_origins: Default::default() })
+    }
+
+    fn read_buildings(&mut self, buildings: &Element) -> XmlResult<Vec<BuildingDto>> {
+        buildings.expect_name("buildings")?;
+        buildings.check_attributes(&[])?;
+        buildings.non_empty_children()?.iter()
+            .map(|b| self.read_building(b))
+            .collect()
+    }
+
+    fn read_building(&mut self, building: &Element) -> XmlResult<BuildingDto> {
+        match building.name.as_str() {
+            "processor" => {
+                building.check_attributes(&["id", "type", "power-usage"])?;
+                Ok(BuildingDto::Processor(ProcessorDto {
+                    id: self.register_id(building)?,
+                    kind: building.enum_attribute("type", &BUILDING_TYPES)?,
+                    power_usage: building.int_attribute("power-usage", None)?,
+                }))
+            }
+            "extractor" => {
+                building.check_attributes(&["id", "type", "power-usage", "normal-extraction-rate"])?;
+                Ok(BuildingDto::Extractor(ExtractorDto {
+                    id: self.register_id(building)?,
+                    kind: building.enum_attribute("type", &BUILDING_TYPES)?,
+                    power_usage: building.int_attribute("power-usage", None)?,
+                    normal_extraction_rate: building.positive_attribute("normal-extraction-rate")?,
+                }))
+            }
+            name => Err(format!("line {} : expected <processor> or <extractor> but found <{}>", building.line, name))
+        }
+    }
+
+    fn read_items(&mut self, items: &Element, buildings: &mut Vec<BuildingDto>) -> XmlResult<Vec<ItemDto>> {
+        items.expect_name("items")?;
+        items.check_attributes(&[])?;
+        items.non_empty_children()?.iter()
+            .map(|i| self.read_item(i, buildings))
+            .collect()
+    }
+
+    fn read_item(&mut self, item: &Element, buildings: &mut Vec<BuildingDto>) -> XmlResult<ItemDto> {
+        match item.name.as_str() {
+            "item" => {
+                item.check_attributes(&["id", "display-name", "form", "stack-size", "sink-points", "energy", "radioactivity"])?;
+                Ok(ItemDto::Product(ProductDto { id: self.register_id(item)?, form: item.form()?, names: item.names(), metadata: item.metadata()? }))
+            }
+            "resource" => {
+                item.check_attributes(&["id", "display-name", "form", "stack-size", "sink-points", "energy", "radioactivity", "impure", "normal", "pure", "extractor"])?;
+                let extractor = item.enum_attribute("extractor", &RESOURCE_EXTRACTORS)?;
+                Ok(ItemDto::Resource(ResourceDto {
+                    id: self.register_id(item)?,
+                    form: item.form()?,
+                    extractor: resolve_extractor(item, &extractor, buildings)?,
+                    extractors: extractors_of_type(&extractor, buildings),
+                    impure: item.int_attribute("impure", Some(-1))?,
+                    normal: item.int_attribute("normal", Some(-1))?,
+                    pure: item.int_attribute("pure", Some(-1))?,
+                    names: item.names(),
+                    metadata: item.metadata()?,
+                }))
+            }
+            name => Err(format!("line {} : expected <resource> or <item> but found <{}>", item.line, name))
+        }
+    }
+
+    fn read_recipes(&mut self, recipes: &Element, buildings: &[BuildingDto], items: &[ItemDto]) -> XmlResult<Vec<RecipeDto>> {
+        recipes.expect_name("recipes")?;
+        recipes.check_attributes(&[])?;
+        recipes.non_empty_children()?.iter()
+            .map(|r| self.read_recipe(r, buildings, items))
+            .collect()
+    }
+
+    fn read_recipe(&mut self, recipe: &Element, buildings: &[BuildingDto], items: &[ItemDto]) -> XmlResult<RecipeDto> {
+        recipe.expect_name("recipe")?;
+        recipe.check_attributes(&["id", "display-name", "duration", "building", "alternate"])?;
+
+        let id = self.register_id(recipe)?;
+        let building = recipe.required_attribute("building")?.to_string();
+        if !buildings.iter().any(|b| b.id() == building) {
+            return Err(format!("line {} : recipe '{}' refers to the unknown building '{}'", recipe.line, id, building));
+        }
+
+        let (inputs, outputs) = match recipe.children.as_slice() {
+            [first, second] if first.name == "inputs" && second.name == "outputs" => (first, second),
+            [first, second] if first.name == "outputs" && second.name == "inputs" => (second, first),
+            _ => return Err(format!("line {} : <recipe> must contain exactly one <inputs> and one <outputs>", recipe.line))
+        };
+
+        Ok(RecipeDto {
+            //ids of recipes are prefixed with '_' in the XML book to not collide with the ids of the items
+            id: id.strip_prefix('_').map(|i| i.to_string()).unwrap_or(id),
+            duration: recipe.positive_attribute("duration")?,
+            building,
+            alternate: recipe.bool_attribute("alternate")?,
+            inputs: read_reactants(inputs, items, false)?,
+            outputs: read_reactants(outputs, items, true)?,
+            names: recipe.names(),
+        })
+    }
+}
+
+/// The outputs of a recipe can be empty for generators since they only produce power
+fn read_reactants(reactants: &Element, items: &[ItemDto], allow_empty: bool) -> XmlResult<Vec<ReactantDto>> {
+    reactants.check_attributes(&[])?;
+    let children = if allow_empty { &reactants.children } else { reactants.non_empty_children()? };
+    children.iter()
+        .map(|r| read_reactant(r, items))
+        .collect()
+}
+
+fn read_reactant(reactant: &Element, items: &[ItemDto]) -> XmlResult<ReactantDto> {
+    reactant.expect_name("reactant")?;
+    reactant.check_attributes(&["item", "quantity"])?;
+    let item_id = reactant.required_attribute("item")?.to_string();
+    if !items.iter().any(|i| i.id() == item_id) {
+        return Err(format!("line {} : reactant refers to the unknown item '{}'", reactant.line, item_id));
+    }
+    Ok(ReactantDto { item_id, quantity: reactant.positive_attribute("quantity")? })
+}
+
+/// In the XML book, the extractor of a resource is a type of building
+/// while the JSON book uses the id of the building.
+fn resolve_extractor(resource: &Element, extractor: &str, buildings: &mut Vec<BuildingDto>) -> XmlResult<String> {
+    match extractor {
+        "miner" => Ok(extractor.to_string()),
+        "manual" => {
+            if !buildings.iter().any(|b| b.id() == "manual") {
+                buildings.push(BuildingDto::Extractor(ExtractorDto {
+                    id: "manual".to_string(),
+                    kind: "miner".to_string(),
+                    power_usage: 0,
+                    normal_extraction_rate: 0,
+                }))
+            }
+            Ok(extractor.to_string())
+        }
+        kind => buildings.iter()
+            .find_map(|b| match b {
+                BuildingDto::Extractor(e) if e.kind == kind => Some(e.id.clone()),
+                _ => None
+            })
+            .ok_or_else(|| format!("line {} : no extractor of type '{}' is defined for resource '{}'", resource.line, kind, resource.attribute("id").unwrap_or_default()))
+    }
+}
+
+/// All the extractors of the type of a resource, like the tiers of miners
+fn extractors_of_type(kind: &str, buildings: &[BuildingDto]) -> Vec<String> {
+    buildings.iter()
+        .filter_map(|b| match b {
+            //the manual extractor has the miner type but taps no node
+            BuildingDto::Extractor(e) if e.kind == kind && e.id != "manual" => Some(e.id.clone()),
+            _ => None
+        })
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use crate::model::dto::book::BookDto;
+    use crate::model::dto::item::{FormDto, ItemDto, ProductDto, ResourceDto};
+    use crate::model::dto::xml_book::parse_xml;
+
+    #[test]
+    fn xml_book_matches_json_book() {
+        let xml_book = parse_xml(include_str!("../../../recipes/book_update5.xml"), "book_update5.xml").unwrap();
+        let json_book = BookDto::parse().unwrap();
+
+        let xml_recipes: HashSet<&str> = xml_book.recipes.iter().map(|r| r.id.as_str()).collect();
+        let json_recipes: HashSet<&str> = json_book.recipes.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(xml_recipes, json_recipes);
+
+        let xml_items: HashSet<&str> = xml_book.items.iter().map(|i| i.id()).collect();
+        let json_items: HashSet<&str> = json_book.items.iter().map(|i| i.id()).collect();
+        assert_eq!(xml_items, json_items);
+
+        let fluids = |book: &BookDto| book.items.iter()
+            .filter(|i| matches!(i, ItemDto::Product(ProductDto { form: FormDto::Fluid, .. }) | ItemDto::Resource(ResourceDto { form: FormDto::Fluid, .. })))
+            .map(|i| i.id().to_string())
+            .collect::<HashSet<String>>();
+        assert_eq!(fluids(&xml_book), fluids(&json_book));
+        assert!(fluids(&json_book).contains("water"));
+
+        //both books list the tiers of miners of the resources
+        let tiers = |book: &BookDto| book.items.iter()
+            .filter_map(|i| match i {
+                ItemDto::Resource(r) if r.id == "iron_ore" => Some(r.extractors.clone()),
+                _ => None
+            })
+            .collect::<Vec<Vec<String>>>();
+        assert_eq!(tiers(&xml_book), tiers(&json_book));
+        assert_eq!(tiers(&json_book), vec![vec!["miner_mk1", "miner_mk2", "miner_mk3"]]);
+
+        assert!(xml_book.to_full_book().is_ok());
+    }
+
+    #[test]
+    fn xml_book_requires_a_name() {
+        let content = r#"<book xmlns="pero:book"><buildings/><items/><recipes/></book>"#;
+        let error = parse_xml(content, "test").err().unwrap();
+        assert!(error.to_string().contains("missing required attribute 'name'"));
+    }
+}
@@ -0,0 +1,196 @@
+use std::fmt::{Display, Formatter};
+
+use crate::constants::is_nil;
+use crate::model::building::{Building, Extractor};
+use crate::model::clock_speed::ClockSpeed;
+use crate::model::item::{Item, Nodes, Resource};
+
+/// The highest clock speed of an extractor, in percent
+const MAX_CLOCK_SPEED: f64 = 250f64;
+
+/// The purity of a resource node, it multiplies the extraction rate of the extractor
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum Purity {
+    Impure,
+    Normal,
+    Pure,
+}
+
+impl Purity {
+    //the best purity first
+    const ALL: [Purity; 3] = [Purity::Pure, Purity::Normal, Purity::Impure];
+
+    fn factor(&self) -> f64 {
+        match self {
+            Purity::Impure => 0.5,
+            Purity::Normal => 1f64,
+            Purity::Pure => 2f64,
+        }
+    }
+
+    /// The most a node can deliver per minute, the same limits as `Nodes::max_quantity_per_minute`
+    fn max_rate(&self) -> f64 {
+        match self {
+            Purity::Impure => 300f64,
+            Purity::Normal => 600f64,
+            Purity::Pure => 780f64,
+        }
+    }
+
+    /// The most the fastest extractor, overclocked, delivers from a node of this purity
+    fn deliverable_rate(&self, extractors: &[Extractor]) -> f64 {
+        match extractors.last() {
+            Some(fastest) => self.max_rate().min(fastest.normal_extraction_rate() as f64 * self.factor() * MAX_CLOCK_SPEED / 100f64),
+            None => self.max_rate(),
+        }
+    }
+
+    fn count(&self, nodes: &Nodes) -> u32 {
+        match self {
+            Purity::Impure => nodes.impure(),
+            Purity::Normal => nodes.normal(),
+            Purity::Pure => nodes.pure(),
+        }
+    }
+}
+
+impl Display for Purity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Purity::Impure => f.write_str("impure"),
+            Purity::Normal => f.write_str("normal"),
+            Purity::Pure => f.write_str("pure"),
+        }
+    }
+}
+
+/// Extractors of the same tier and clock speed tapping nodes of the same purity
+#[derive(Debug)]
+pub struct NodeAllocation {
+    pub resource: Item,
+    //none for the resources extracted anywhere, like water
+    pub purity: Option<Purity>,
+    pub extractor: Building,
+    pub clock_speed: ClockSpeed,
+    pub count: u32,
+}
+
+impl NodeAllocation {
+    pub fn power_usage(&self) -> f64 {
+        self.clock_speed.power_usage(&self.extractor) * (self.count as f64)
+    }
+}
+
+/// Tap the nodes of the best purity first so that the fewest nodes are used. Each node gets
+/// the slowest extractor able to deliver its share at 100%, or the fastest one overclocked.
+/// The amount the extractors can not deliver from the nodes, even overclocked, is returned with them
+pub fn allocate_nodes(item: &Item, amount: f64) -> (Vec<NodeAllocation>, f64) {
+    let resource = match item.as_resource() {
+        Some(r) if !item.is_resource_picked_manually() && !is_nil(amount) => r,
+        _ => return (vec![], 0f64)
+    };
+    let fastest = match resource.extractors().last() {
+        Some(e) => e,
+        None => return (vec![], 0f64)
+    };
+
+    let nodes = match resource.nodes() {
+        Some(nodes) => nodes,
+        None => {
+            let rate = fastest.normal_extraction_rate() as f64;
+            let count = (amount / rate).ceil();
+            return (tap(item, None, fastest, amount / count, count as u32).into_iter().collect(), 0f64);
+        }
+    };
+
+    let mut allocations = vec![];
+    let mut remaining = amount;
+    for purity in Purity::ALL {
+        if is_nil(remaining) {
+            break;
+        }
+        let per_node = purity.deliverable_rate(resource.extractors());
+        let full_nodes = ((remaining / per_node).floor() as u32).min(purity.count(nodes));
+        if full_nodes > 0 {
+            allocations.extend(tap(item, Some(purity), best_extractor(resource.extractors(), purity, per_node), per_node, full_nodes));
+            remaining -= per_node * (full_nodes as f64);
+        }
+        if full_nodes < purity.count(nodes) && !is_nil(remaining) {
+            allocations.extend(tap(item, Some(purity), best_extractor(resource.extractors(), purity, remaining), remaining, 1));
+            remaining = 0f64;
+        }
+    }
+    (allocations, if is_nil(remaining) { 0f64 } else { remaining })
+}
+
+/// The most the extractors of the book can deliver per minute from all the nodes of a resource,
+/// none for the resources extracted anywhere
+pub fn nodes_capacity(resource: &Resource) -> Option<f64> {
+    let nodes = resource.nodes()?;
+    Some(Purity::ALL.iter()
+        .map(|purity| purity.count(nodes) as f64 * purity.deliverable_rate(resource.extractors()))
+        .sum())
+}
+
+/// The slowest extractor delivering the amount without overclocking, or the fastest one
+fn best_extractor(extractors: &[Extractor], purity: Purity, amount: f64) -> &Extractor {
+    extractors.iter()
+        .find(|e| e.normal_extraction_rate() as f64 * purity.factor() >= amount)
+        .unwrap_or_else(|| &extractors[extractors.len() - 1])
+}
+
+fn tap(item: &Item, purity: Option<Purity>, extractor: &Extractor, per_node: f64, count: u32) -> Option<NodeAllocation> {
+    let rate = extractor.normal_extraction_rate() as f64 * purity.map_or(1f64, |p| p.factor());
+    let clock_speed = ClockSpeed::new((per_node / rate * 100f64).clamp(1f64, MAX_CLOCK_SPEED)).ok()?;
+    Some(NodeAllocation { resource: item.clone(), purity, extractor: Building::Extractor(extractor.clone()), clock_speed, count })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::model::building::Extractor;
+    use crate::model::extraction::{allocate_nodes, nodes_capacity, Purity};
+    use crate::model::item::{Form, Item, ItemMetadata, Nodes, Resource};
+    use crate::model::names::Names;
+
+    #[test]
+    fn best_purity_first() {
+        let miner = |tier: u32, power_usage: i32, rate: u32| Extractor::new(format!("miner_mk{}", tier), "miner".to_string(), power_usage, rate);
+        let extractors = vec![miner(1, 5, 60), miner(2, 12, 120), miner(3, 30, 240)];
+        let iron_ore = Item::Resource(Resource::new("iron_ore".to_string(), Form::Solid, extractors, Some(Nodes::new(2, 1, 1)),
+                                                    Names::new(None, HashMap::new()), ItemMetadata::default()));
+
+        let (allocations, remainder) = allocate_nodes(&iron_ore, 1000f64);
+
+        let summary: Vec<(Option<Purity>, &str, String, u32)> = allocations.iter()
+            .map(|a| (a.purity, a.extractor.id(), a.clock_speed.to_string(), a.count))
+            .collect();
+        assert_eq!(summary, vec![
+            (Some(Purity::Pure), "miner_mk3", "162.5%".to_string(), 1),
+            (Some(Purity::Normal), "miner_mk3", "91.67%".to_string(), 1),
+        ]);
+
+        assert_eq!(remainder, 0f64);
+
+        let (allocations, _) = allocate_nodes(&iron_ore, 100f64);
+        assert_eq!(allocations[0].extractor.id(), "miner_mk1");
+        assert_eq!(allocations[0].clock_speed.to_string(), "83.33%");
+    }
+
+    #[test]
+    fn unreachable_node_capacity() {
+        //an overclocked oil extractor delivers 600 per minute on a pure node, less than its 780
+        let oil_extractor = Extractor::new("oil_extractor".to_string(), "miner".to_string(), 40, 120);
+        let crude_oil = Item::Resource(Resource::new("crude_oil".to_string(), Form::Fluid, vec![oil_extractor], Some(Nodes::new(0, 0, 1)),
+                                                     Names::new(None, HashMap::new()), ItemMetadata::default()));
+
+        let (allocations, remainder) = allocate_nodes(&crude_oil, 780f64);
+
+        assert_eq!(allocations.len(), 1);
+        assert_eq!(allocations[0].clock_speed.to_string(), "250%");
+        assert!((remainder - 180f64).abs() < 1e-9);
+        assert_eq!(crude_oil.as_resource().and_then(nodes_capacity), Some(600f64));
+    }
+}
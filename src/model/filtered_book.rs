@@ -18,7 +18,7 @@ impl<'a> FilteredBook<'a> {
 }
 
 impl FilterableBook for FilteredBook<'_> {
-    fn filter(&self, predicate: &RecipeFilter) -> Result<FilteredBook> {
+    fn filter(&self, predicate: &RecipeFilter) -> Result<FilteredBook<'_>> {
         let mut new_recipes = Vec::<usize>::new();
 
         for index in &self.filtered_recipe_indices {
@@ -59,6 +59,10 @@ impl Book for FilteredBook<'_> {
     fn get_item_by_id(&self, item_id: &str) -> Result<&Item> {
         self.full_book.get_item_by_id(item_id)
     }
+
+    fn get_recipe_by_id(&self, recipe_id: &str) -> Result<&Recipe> {
+        self.full_book.get_recipe_by_id(recipe_id)
+    }
 }
 
 impl FilteredBook<'_> {
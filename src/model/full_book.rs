@@ -3,6 +3,7 @@ use crate::model::book::{Book, FilterableBook};
 use crate::error::{Error, Result};
 use crate::error::Error::{InvalidRecipeIndex};
 use crate::model::dto::book::BookDto;
+use crate::model::dto::overlay::OverlayOrigins;
 use crate::model::filtered_book::FilteredBook;
 use crate::model::item::Item;
 use crate::model::recipe::Recipe;
@@ -10,11 +11,32 @@ use crate::recipe_filter::RecipeFilter;
 
 
 pub struct FullBook {
+    name:String,
+    version:Option<String>,
     items:HashMap<String,Item>,
-    recipes:Vec<Recipe>
+    recipes:Vec<Recipe>,
+    overlay_origins:OverlayOrigins,
 }
 
 impl FullBook {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn version(&self) -> Option<&str> {
+        self.version.as_deref()
+    }
+    /// The name of the overlay the recipe comes from, if any
+    pub fn recipe_overlay(&self, recipe_id: &str) -> Option<&str> {
+        self.overlay_origins.recipes.get(recipe_id).map(|o| o.as_str())
+    }
+    /// The name of the overlay the item comes from, if any
+    pub fn item_overlay(&self, item_id: &str) -> Option<&str> {
+        self.overlay_origins.items.get(item_id).map(|o| o.as_str())
+    }
+    /// The version of the book, or its name if it has no version
+    pub fn identifier(&self) -> &str {
+        self.version().unwrap_or(&self.name)
+    }
     pub fn items(&self) -> &HashMap<String, Item> {
         &self.items
     }
@@ -32,13 +54,13 @@ impl FullBook {
     }
 
 
-    pub fn new(items: HashMap<String, Item>, recipes: Vec<Recipe>) -> Self {
-        FullBook { items, recipes }
+    pub fn new(name: String, version: Option<String>, items: HashMap<String, Item>, recipes: Vec<Recipe>, overlay_origins: OverlayOrigins) -> Self {
+        FullBook { name, version, items, recipes, overlay_origins }
     }
 }
 
 impl FilterableBook for FullBook {
-    fn filter(&self, predicate: &RecipeFilter) -> Result<FilteredBook> {
+    fn filter(&self, predicate: &RecipeFilter) -> Result<FilteredBook<'_>> {
         let filtered_recipes = self.recipes
             .iter()
             .enumerate()
@@ -66,8 +88,25 @@ impl Book for FullBook {
             .collect())
     }
 
+    /// Also accept the display name of the item, ignoring the case, as long as a single item has it
     fn get_item_by_id(&self, item_id:&str) -> Result<&Item> {
-        self.items.get(item_id).ok_or_else(|| Error::UnknownItem(item_id.to_string()))
+        if let Some(item) = self.items.get(item_id) {
+            return Ok(item);
+        }
+
+        let mut named: Vec<&Item> = self.items.values().filter(|i| i.names().matches(item_id)).collect();
+        named.sort_by(|i1, i2| i1.id().cmp(i2.id()));
+        match named.as_slice() {
+            [] => Err(Error::UnknownItem(item_id.to_string())),
+            [item] => Ok(item),
+            _ => Err(Error::AmbiguousItem(item_id.to_string(), named.iter().map(|i| i.id().to_string()).collect()))
+        }
+    }
+
+    fn get_recipe_by_id(&self, recipe_id: &str) -> Result<&Recipe> {
+        self.recipes.iter()
+            .find(|r| r.id() == recipe_id)
+            .ok_or_else(|| Error::UnknownRecipe(recipe_id.to_string()))
     }
 }
 
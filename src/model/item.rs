@@ -2,6 +2,7 @@ use std::fmt::{Display, Formatter};
 use std::hash::{Hash, Hasher};
 
 use crate::model::building::Extractor;
+use crate::model::names::Names;
 
 #[derive(Clone,Eq,Debug)]
 pub enum Item {
@@ -9,17 +10,58 @@ pub enum Item {
     Product(Product),
 }
 
-#[derive(Clone,Eq,Debug)]
+/// Fluids are counted in m³ and transported in pipes
+#[derive(Clone,Copy,Eq,PartialEq,Hash,Debug)]
+pub enum Form {
+    Solid,
+    Fluid,
+}
+
+#[derive(Clone,Debug)]
 pub struct Product {
-    id:String
+    id:String,
+    form:Form,
+    names:Names,
+    metadata:ItemMetadata,
 }
 
-#[derive(Clone,Eq, Debug)]
+#[derive(Clone, Debug)]
 #[allow(dead_code)]
 pub struct Resource {
     id:String,
-    extractor:Extractor,
+    form:Form,
+    //the extractors able to tap the resource, slowest first
+    extractors:Vec<Extractor>,
     nodes:Option<Nodes>,
+    names:Names,
+    metadata:ItemMetadata,
+}
+
+/// Optional data about an item. The energy is in MJ (per m³ for fluids)
+#[derive(Clone, Debug, Default)]
+pub struct ItemMetadata {
+    stack_size: Option<u32>,
+    sink_points: Option<u32>,
+    energy: Option<f64>,
+    radioactivity: Option<f64>,
+}
+
+impl ItemMetadata {
+    pub fn new(stack_size: Option<u32>, sink_points: Option<u32>, energy: Option<f64>, radioactivity: Option<f64>) -> Self {
+        ItemMetadata { stack_size, sink_points, energy, radioactivity }
+    }
+    pub fn stack_size(&self) -> Option<u32> {
+        self.stack_size
+    }
+    pub fn sink_points(&self) -> Option<u32> {
+        self.sink_points
+    }
+    pub fn energy(&self) -> Option<f64> {
+        self.energy
+    }
+    pub fn radioactivity(&self) -> Option<f64> {
+        self.radioactivity
+    }
 }
 
 impl Item {
@@ -32,7 +74,7 @@ impl Item {
 
     pub fn is_resource_picked_manually(&self) -> bool {
         match self {
-            Item::Resource(r) => r.extractor.is_manual(),
+            Item::Resource(r) => r.extractors.iter().any(|e| e.is_manual()),
             Item::Product(_) => false
         }
     }
@@ -42,6 +84,9 @@ impl Resource {
     pub fn nodes(&self) -> Option<&Nodes> {
         self.nodes.as_ref()
     }
+    pub fn extractors(&self) -> &[Extractor] {
+        &self.extractors
+    }
 }
 
 #[derive(Clone,Eq, Debug)]
@@ -55,10 +100,23 @@ impl Nodes {
     pub fn new(impure: u32, normal: u32, pure: u32) -> Self {
         Nodes { impure, normal, pure }
     }
+    pub fn impure(&self) -> u32 {
+        self.impure
+    }
+    pub fn normal(&self) -> u32 {
+        self.normal
+    }
+    pub fn pure(&self) -> u32 {
+        self.pure
+    }
 }
 
 
 
+impl Eq for Product {}
+
+impl Eq for Resource {}
+
 impl PartialEq for Item {
     fn eq(&self, other: &Self) -> bool {
         match (&self,&other) {
@@ -119,13 +177,22 @@ impl Display for Item {
 
 
 impl Resource {
-    pub fn new(id: String, extractor: Extractor, nodes:Option<Nodes>) -> Self {
-        Resource { id, extractor, nodes }
+    pub fn new(id: String, form: Form, extractors: Vec<Extractor>, nodes:Option<Nodes>, names:Names, metadata:ItemMetadata) -> Self {
+        Resource { id, form, extractors, nodes, names, metadata }
     }
 
     pub fn max_quantity_per_minute(&self) -> Option<u32> {
         self.nodes().map(|n| n.max_quantity_per_minute())
     }
+
+    /// The power in MW needed to extract one unit per minute, an estimate made with the
+    /// extractor using the most power per unit, on a normal node at 100%
+    pub fn extraction_power(&self) -> f64 {
+        self.extractors.iter()
+            .filter(|e| e.normal_extraction_rate() > 0)
+            .map(|e| e.power_usage() as f64 / e.normal_extraction_rate() as f64)
+            .fold(0f64, f64::max)
+    }
 }
 
 impl Nodes {
@@ -135,8 +202,8 @@ impl Nodes {
 }
 
 impl Product {
-    pub fn new(id: String) -> Self {
-        Product { id }
+    pub fn new(id: String, form: Form, names:Names, metadata:ItemMetadata) -> Self {
+        Product { id, form, names, metadata }
     }
 }
 
@@ -148,5 +215,30 @@ impl Item {
             Item::Product(i) => &i.id
         }
     }
+
+    pub fn form(&self) -> Form {
+        match self {
+            Item::Resource(r) => r.form,
+            Item::Product(i) => i.form
+        }
+    }
+
+    pub fn is_fluid(&self) -> bool {
+        self.form() == Form::Fluid
+    }
+
+    pub fn names(&self) -> &Names {
+        match self {
+            Item::Resource(r) => &r.names,
+            Item::Product(i) => &i.names
+        }
+    }
+
+    pub fn metadata(&self) -> &ItemMetadata {
+        match self {
+            Item::Resource(r) => &r.metadata,
+            Item::Product(i) => &i.metadata
+        }
+    }
 }
 
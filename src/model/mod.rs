@@ -10,5 +10,11 @@ pub mod recipe_complexity;
 pub mod bom;
 pub mod bom_printer;
 pub mod amount_format;
-mod dto;
+pub mod clock_speed;
+pub mod extraction;
+pub mod name_format;
+pub mod names;
+pub mod pipe;
+pub mod rate;
+pub mod dto;
 
@@ -0,0 +1,25 @@
+use crate::model::item::Item;
+use crate::model::recipe::Recipe;
+
+/// How items and recipes are named in the outputs
+#[derive(Clone)]
+pub enum NameFormat {
+    Ids,
+    DisplayNames(Option<String>),
+}
+
+impl NameFormat {
+    pub fn item<'a>(&self, item: &'a Item) -> &'a str {
+        match self {
+            NameFormat::Ids => item.id(),
+            NameFormat::DisplayNames(locale) => item.names().get(locale.as_deref()).unwrap_or_else(|| item.id())
+        }
+    }
+
+    pub fn recipe<'a>(&self, recipe: &'a Recipe) -> &'a str {
+        match self {
+            NameFormat::Ids => recipe.id(),
+            NameFormat::DisplayNames(locale) => recipe.names().get(locale.as_deref()).unwrap_or_else(|| recipe.id())
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use std::collections::HashMap;
+
+/// The human-readable names of an item or a recipe.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct Names {
+    display_name: Option<String>,
+    localized_names: HashMap<String, String>,
+}
+
+impl Names {
+    pub fn new(display_name: Option<String>, localized_names: HashMap<String, String>) -> Self {
+        Names { display_name, localized_names }
+    }
+
+    /// The name to display for the given locale. A locale like `pt_BR` falls back
+    /// to `pt`, then to the default display name
+    pub fn get(&self, locale: Option<&str>) -> Option<&str> {
+        let localized = locale.and_then(|locale| {
+            let language = locale.split(['_', '-', '.']).next().unwrap_or(locale);
+            self.localized_names.get(locale).or_else(|| self.localized_names.get(language))
+        });
+        localized.or(self.display_name.as_ref()).map(|n| n.as_str())
+    }
+
+    /// Check if one of the names is the given one, ignoring the case
+    pub fn matches(&self, name: &str) -> bool {
+        let name = name.to_lowercase();
+        self.display_name.iter()
+            .chain(self.localized_names.values())
+            .any(|n| n.to_lowercase() == name)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use maplit::hashmap;
+
+    use crate::model::names::Names;
+
+    #[test]
+    fn localized_name_lookup() {
+        let names = Names::new(Some("Iron Plate".to_string()), hashmap! {
+            "fr".to_string() => "Plaque de fer".to_string(),
+            "pt_BR".to_string() => "Placa de Ferro".to_string(),
+        });
+
+        assert_eq!(names.get(None), Some("Iron Plate"));
+        assert_eq!(names.get(Some("fr_FR.UTF-8")), Some("Plaque de fer"));
+        assert_eq!(names.get(Some("pt_BR")), Some("Placa de Ferro"));
+        assert_eq!(names.get(Some("de")), Some("Iron Plate"));
+        assert!(names.matches("plaque DE FER"));
+        assert!(!names.matches("iron_plate"));
+    }
+}
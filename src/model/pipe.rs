@@ -0,0 +1,64 @@
+use std::fmt::{Display, Formatter};
+
+use crate::constants::is_nil;
+
+/// The pipelines used to transport fluids
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum Pipe {
+    Mk1,
+    Mk2,
+}
+
+impl Pipe {
+    pub const ALL: [Pipe; 2] = [Pipe::Mk1, Pipe::Mk2];
+
+    /// The capacity of the pipe in m³/min
+    pub fn capacity(&self) -> f64 {
+        match self {
+            Pipe::Mk1 => 300f64,
+            Pipe::Mk2 => 600f64,
+        }
+    }
+
+    /// The number of pipes of this kind needed to carry the given amount of fluid per minute
+    pub fn lines_needed(&self, amount_per_minute: f64) -> u32 {
+        let lines = amount_per_minute / self.capacity();
+        if is_nil(lines - lines.round()) {
+            lines.round().max(0f64) as u32
+        } else {
+            lines.ceil().max(0f64) as u32
+        }
+    }
+
+    /// The smallest pipe that carries the given amount with a single line,
+    /// or the number of lines of the best pipe if one is not enough
+    pub fn for_amount(amount_per_minute: f64) -> (Pipe, u32) {
+        Pipe::ALL.iter()
+            .find(|p| p.lines_needed(amount_per_minute) <= 1)
+            .map(|p| (*p, 1))
+            .unwrap_or_else(|| (Pipe::Mk2, Pipe::Mk2.lines_needed(amount_per_minute)))
+    }
+}
+
+impl Display for Pipe {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Pipe::Mk1 => f.write_str("Pipeline Mk.1"),
+            Pipe::Mk2 => f.write_str("Pipeline Mk.2"),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::model::pipe::Pipe;
+
+    #[test]
+    fn pipes_for_amount() {
+        assert_eq!(Pipe::for_amount(120f64), (Pipe::Mk1, 1));
+        assert_eq!(Pipe::for_amount(300f64), (Pipe::Mk1, 1));
+        assert_eq!(Pipe::for_amount(300.5f64), (Pipe::Mk2, 1));
+        assert_eq!(Pipe::for_amount(1500f64), (Pipe::Mk2, 3));
+    }
+}
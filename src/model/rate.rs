@@ -0,0 +1,273 @@
+use std::fmt::{Display, Formatter};
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+use std::str::FromStr;
+
+use num_rational::Ratio;
+use num_traits::{CheckedAdd, CheckedMul, CheckedSub, Signed, ToPrimitive, Zero};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::error::Error;
+
+/// The relative precision of the amounts of a solution, the solver returns f32 values
+const SNAP_TOLERANCE: f64 = 1e-7;
+/// The largest denominator of a rate recovered from a solution
+const MAX_DENOMINATOR: i64 = 1_000_000;
+/// The operators panic rather than wrap around when a fraction overflows, the balances of
+/// a solution use the checked operations instead
+const OVERFLOW: &str = "rate overflow";
+
+/// An exact amount per minute. It is parsed from a decimal (`7.5`) or a fraction (`15/2`)
+/// followed by an optional time unit : `/s`, `/min` (the default) or `/h`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Rate(Ratio<i64>);
+
+impl Rate {
+    pub fn per_minute(amount: i64) -> Self {
+        Rate(Ratio::from_integer(amount))
+    }
+
+    pub fn new(numerator: i64, denominator: i64) -> Self {
+        Rate(Ratio::new(numerator, denominator))
+    }
+
+    pub fn as_f64(&self) -> f64 {
+        self.0.to_f64().unwrap_or(f64::NAN)
+    }
+
+    pub fn ratio(&self) -> Ratio<i64> {
+        self.0
+    }
+
+    /// The simplest fraction within the precision of the solver, to recover the exact
+    /// amounts of a solution computed with floats (the convergents of its continued fraction)
+    pub fn snapped(value: f64) -> Self {
+        if !value.is_finite() {
+            return Rate::default();
+        }
+        let target = value.abs();
+        let tolerance = SNAP_TOLERANCE * target.max(1f64);
+        let (mut h0, mut h1, mut k0, mut k1) = (0i64, 1i64, 1i64, 0i64);
+        let mut x = target;
+        loop {
+            //the floats of 1/(x-a) can fall just below an integer
+            let a = if (x - x.round()).abs() < 1e-9 { x.round() } else { x.floor() };
+            let (h, k) = (a as i64 * h1 + h0, a as i64 * k1 + k0);
+            if k > MAX_DENOMINATOR || k <= 0 {
+                break;
+            }
+            (h0, h1, k0, k1) = (h1, h, k1, k);
+            if (h1 as f64 / k1 as f64 - target).abs() < tolerance || (x - a).abs() < 1e-9 {
+                break;
+            }
+            x = 1f64 / (x - a);
+        }
+        let ratio = if k1 == 0 { Ratio::zero() } else { Ratio::new(h1, k1) };
+        Rate(if value < 0f64 { -ratio } else { ratio })
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0.is_zero()
+    }
+
+    pub fn is_positive(&self) -> bool {
+        self.0.is_positive()
+    }
+
+    /// The sum of two rates, none when it does not fit in the fraction
+    pub fn checked_add(&self, rhs: Rate) -> Option<Rate> {
+        self.0.checked_add(&rhs.0).map(Rate)
+    }
+
+    /// The difference of two rates, none when it does not fit in the fraction
+    pub fn checked_sub(&self, rhs: Rate) -> Option<Rate> {
+        self.0.checked_sub(&rhs.0).map(Rate)
+    }
+
+    /// The product of two rates, none when it does not fit in the fraction
+    pub fn checked_mul(&self, rhs: Rate) -> Option<Rate> {
+        self.0.checked_mul(&rhs.0).map(Rate)
+    }
+}
+
+
+impl Default for Rate {
+    fn default() -> Self {
+        Rate::per_minute(0)
+    }
+}
+
+impl AddAssign for Rate {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs
+    }
+}
+
+impl SubAssign for Rate {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs
+    }
+}
+
+impl Add for Rate {
+    type Output = Rate;
+
+    fn add(self, rhs: Self) -> Rate {
+        self.checked_add(rhs).expect(OVERFLOW)
+    }
+}
+
+impl Sub for Rate {
+    type Output = Rate;
+
+    fn sub(self, rhs: Self) -> Rate {
+        self.checked_sub(rhs).expect(OVERFLOW)
+    }
+}
+
+impl Neg for Rate {
+    type Output = Rate;
+
+    fn neg(self) -> Rate {
+        Rate(-self.0)
+    }
+}
+
+/// The amount of a reactant for a number of recipe executions
+impl Mul<u32> for Rate {
+    type Output = Rate;
+
+    fn mul(self, rhs: u32) -> Rate {
+        self * Rate::per_minute(rhs as i64)
+    }
+}
+
+impl Mul for Rate {
+    type Output = Rate;
+
+    fn mul(self, rhs: Rate) -> Rate {
+        self.checked_mul(rhs).expect(OVERFLOW)
+    }
+}
+
+impl Sum for Rate {
+    fn sum<I: Iterator<Item=Rate>>(iter: I) -> Rate {
+        iter.fold(Rate::default(), |r1, r2| r1 + r2)
+    }
+}
+
+impl FromStr for Rate {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::RateParsingFailed(s.to_string());
+        let value = s.trim();
+        let (number, per_minute) = [("/s", Ratio::from_integer(60)), ("/min", Ratio::from_integer(1)), ("/h", Ratio::new(1, 60))]
+            .iter()
+            .find_map(|(unit, factor)| value.strip_suffix(unit).map(|n| (n, *factor)))
+            .unwrap_or((value, Ratio::from_integer(1)));
+
+        let amount = match number.split_once('/') {
+            Some((numerator, denominator)) => {
+                let denominator = parse_decimal(denominator).filter(|d| *d != Ratio::from_integer(0)).ok_or_else(invalid)?;
+                parse_decimal(numerator).ok_or_else(invalid)? / denominator
+            }
+            None => parse_decimal(number).ok_or_else(invalid)?
+        };
+
+        if amount < Ratio::from_integer(0) {
+            return Err(invalid());
+        }
+        Ok(Rate(amount * per_minute))
+    }
+}
+
+impl Display for Rate {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Integer rates are written as numbers like before, the others as fractions per minute
+impl Serialize for Rate {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.0.is_integer() {
+            serializer.serialize_i64(self.0.to_integer())
+        } else {
+            serializer.serialize_str(&self.to_string())
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Rate {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum RateDto {
+            Integer(u64),
+            Number(f64),
+            Text(String),
+        }
+
+        let text = match RateDto::deserialize(deserializer)? {
+            RateDto::Integer(value) => value.to_string(),
+            RateDto::Number(value) => value.to_string(),
+            RateDto::Text(value) => value,
+        };
+        text.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Parse a decimal number like `6.000000` into an exact ratio
+pub(crate) fn parse_decimal(value: &str) -> Option<Ratio<i64>> {
+    let value = value.trim();
+    let (integer, decimals) = value.split_once('.').unwrap_or((value, ""));
+    let denominator = 10i64.checked_pow(decimals.len() as u32)?;
+    let integer = if integer.is_empty() || integer == "-" { 0 } else { integer.parse::<i64>().ok()? };
+    let decimals = if decimals.is_empty() { 0 } else { decimals.parse::<i64>().ok()? };
+    let decimals = if value.starts_with('-') { -decimals } else { decimals };
+    Some(Ratio::new(integer * denominator + decimals, denominator))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use num_rational::Ratio;
+
+    use crate::model::rate::Rate;
+
+    #[test]
+    fn parse_rates() {
+        assert_eq!("12".parse::<Rate>().unwrap(), Rate::per_minute(12));
+        assert_eq!("7.5".parse::<Rate>().unwrap(), Rate(Ratio::new(15, 2)));
+        assert_eq!("15/2/min".parse::<Rate>().unwrap(), Rate(Ratio::new(15, 2)));
+        assert_eq!("2/s".parse::<Rate>().unwrap(), Rate::per_minute(120));
+        assert_eq!("0.5/s".parse::<Rate>().unwrap(), Rate::per_minute(30));
+        assert_eq!("90/h".parse::<Rate>().unwrap(), Rate(Ratio::new(3, 2)));
+        assert!("1/0".parse::<Rate>().is_err());
+        assert!("-3".parse::<Rate>().is_err());
+        assert!("3/day".parse::<Rate>().is_err());
+
+        assert_eq!(Rate::snapped(20f64 / 3f64 + 1e-9), Rate(Ratio::new(20, 3)));
+        assert_eq!(Rate::snapped(-7.4999999), Rate(Ratio::new(-15, 2)));
+        assert_eq!(Rate::snapped(0.0005), Rate(Ratio::new(1, 2000)));
+        assert!(Rate::snapped(1e-9).is_zero());
+        assert_eq!(Rate::snapped((1480f32 / 159f32) as f64), Rate(Ratio::new(1480, 159)));
+        assert_eq!(Rate::snapped((2340f32 / 551f32) as f64), Rate(Ratio::new(2340, 551)));
+        assert_eq!(Rate::snapped((1919.9999f32) as f64), Rate::per_minute(1920));
+
+        let rates: Vec<Rate> = serde_json::from_str(r#"[30, 7.5, "2/s"]"#).unwrap();
+        assert_eq!(rates, vec![Rate::per_minute(30), Rate(Ratio::new(15, 2)), Rate::per_minute(120)]);
+        assert_eq!(serde_json::to_string(&rates).unwrap(), r#"[30,"15/2",120]"#);
+    }
+
+    #[test]
+    fn overflowing_rates() {
+        //the denominators are coprime, the one of the sum is their product
+        let sum = Rate::new(1, 9_999_999).checked_add(Rate::new(1, 10_000_000)).unwrap();
+        assert_eq!(sum.checked_add(Rate::new(1, 10_000_001)), None);
+        assert_eq!(Rate::per_minute(i64::MAX).checked_mul(Rate::per_minute(2)), None);
+        assert_eq!(Rate::new(1, 3).checked_sub(Rate::new(1, 2)), Some(Rate::new(-1, 6)));
+        assert!(std::panic::catch_unwind(|| sum + Rate::new(1, 10_000_001)).is_err());
+    }
+}
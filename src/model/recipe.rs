@@ -2,6 +2,7 @@ use std::fmt::{Display, Formatter};
 use std::hash::{Hash, Hasher};
 use crate::model::building::Building;
 use crate::model::item::Item;
+use crate::model::names::Names;
 use crate::model::reactant::Reactant;
 
 
@@ -14,6 +15,7 @@ pub struct Recipe {
     alternate: bool,
     inputs: Vec<Reactant>,
     outputs: Vec<Reactant>,
+    names: Names,
 }
 
 impl Recipe {
@@ -81,8 +83,8 @@ impl Recipe {
 }
 
 impl Recipe {
-    pub fn new(id: String, duration: u32, building: Building, alternate: bool, inputs: Vec<Reactant>, outputs: Vec<Reactant>) -> Self {
-        Recipe { id, duration, building, alternate, inputs, outputs }
+    pub fn new(id: String, duration: u32, building: Building, alternate: bool, inputs: Vec<Reactant>, outputs: Vec<Reactant>, names: Names) -> Self {
+        Recipe { id, duration, building, alternate, inputs, outputs, names }
     }
 
 
@@ -132,8 +134,12 @@ impl Recipe {
     pub fn outputs(&self) -> &[Reactant]{
         &self.outputs
     }
+    pub fn names(&self) -> &Names {
+        &self.names
+    }
 }
 
+#[allow(dead_code)]
 impl Recipe {
 
 
@@ -141,18 +147,18 @@ impl Recipe {
     //IMPROVE find a find to factorize the three methods below
     /// Retrieve the indices in the referenceBook of the items
     /// involved in this recipe
-    pub fn get_involved_items<'a>(&'a self) -> impl Iterator<Item=&Item>  + 'a {
+    pub fn get_involved_items<'a>(&'a self) -> impl Iterator<Item=&'a Item>  + 'a {
         self.get_input_items().chain(self.get_output_items())
     }
 
 
-    pub fn get_input_items<'a>(&'a self) -> impl Iterator<Item=&Item>  + 'a {
+    pub fn get_input_items<'a>(&'a self) -> impl Iterator<Item=&'a Item>  + 'a {
         self.inputs
             .iter()
             .map(|i| i.item())
     }
 
-    pub fn get_output_items<'a>(&'a self) -> impl Iterator<Item=&Item>  + 'a {
+    pub fn get_output_items<'a>(&'a self) -> impl Iterator<Item=&'a Item>  + 'a {
         self.outputs
             .iter()
             .map(|i| i.item())
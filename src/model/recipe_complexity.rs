@@ -43,8 +43,7 @@ struct ItemComplexity {
 
 impl ItemComplexity {
     pub fn compute_recipe_complexity(&self, recipe:&Recipe) -> u32 {
-        recipe.inputs().iter().map(|r| self.complexities.get(r.item_id()))
-            .flatten()
+        recipe.inputs().iter().filter_map(|r| self.complexities.get(r.item_id()))
             .max().cloned()
             .unwrap_or(0)
     }
@@ -1,13 +1,20 @@
 use std::collections::HashMap;
 use std::ops::{Div, Mul};
 
-use good_lp::{Constraint, default_solver, Expression, IntoAffineExpression, ProblemVariables, Solution, SolverModel, Variable};
-use good_lp::solvers::lp_solvers::LpSolution;
+use good_lp::{Constraint, default_solver, ResolutionError, Expression, IntoAffineExpression, ProblemVariables, Solution, SolverModel, Variable, variable};
 use hashlink::LinkedHashMap;
 
 use crate::model::item::{Item};
 use crate::{Bom, Recipe};
 use crate::constants::is_nil;
+use crate::error::Error;
+use crate::model::clock_speed::ClockSpeeds;
+use crate::model::extraction::nodes_capacity;
+use crate::model::rate::Rate;
+use crate::problem_input::{Bounds, ByproductPolicy, Criterion, Objective, PowerSetting, ProblemInput};
+
+/// An upper bound of the number of executions per minute of a recipe
+const MAX_RECIPE_AMOUNT: f64 = 1e6;
 
 pub struct Problem {
     variables: ProblemVariables,
@@ -15,29 +22,104 @@ pub struct Problem {
 }
 
 struct ProblemData {
-    target_items: HashMap<Item, f64>,
+    target_items: HashMap<Item, Bounds>,
     available_items: HashMap<Item, f64>,
     use_abundances: bool,
+    //the max amounts per minute set in the input, on top of the limits of the nodes
+    resource_caps: HashMap<Item, f64>,
+    //the cost of a unit of some resources, replacing their abundance weight
+    resource_weights: HashMap<Item, f64>,
+    power: PowerSetting,
+    //the policy of the byproducts without one of their own
+    byproducts: ByproductPolicy,
+    byproduct_policies: HashMap<Item, ByproductPolicy>,
+    max_power: Option<f64>,
+    objective: Objective,
+    clock_speeds: ClockSpeeds,
 
     recipe_amount: LinkedHashMap<Recipe, Variable>,
     item_count: HashMap<Item, Expression>,
+    //the integer number of buildings of each recipe, empty if the buildings are rounded up after solving
+    building_count: LinkedHashMap<Recipe, Variable>,
+    //whether each recipe is used, only when the number of distinct recipes is minimized
+    recipe_used: LinkedHashMap<Recipe, Variable>,
+    //the factor applied to the target ratios, only when the production is maximized
+    scale: Option<Variable>,
+    //the constraint removed to diagnose an infeasible problem
+    relaxation: Option<Relaxation>,
+    //a constraint given some extra room, to measure how much the objective depends on it
+    loosening: Option<(Relaxation, f64)>,
+}
 
+/// A constraint that can be removed to find out why a problem has no solution
+#[derive(Clone, Debug, PartialEq)]
+pub enum Relaxation {
+    //the extraction limit of a resource
+    ResourceCap(Item),
+    //the amount of an available item, that becomes unlimited
+    AvailableItem(Item),
+    //the max amount of a target, that is then only produced at least at its min
+    TargetMax(Item),
+    //the forbidden leftover of a byproduct
+    Byproduct(Item),
+    //the power produced by the factory or taken from the grid
+    Power,
+    MaxPower,
 }
 
 impl Problem {
-    pub fn new(variables: ProblemVariables,
-               target_items: HashMap<Item, f64>,
+    pub fn new(mut variables: ProblemVariables,
+               target_items: HashMap<Item, Bounds>,
                available_items: HashMap<Item, f64>,
                recipe_amount: LinkedHashMap<Recipe, Variable>,
                item_count: HashMap<Item, Expression>,
-               use_abundances: bool) -> Self {
-        Problem { variables, data: ProblemData { target_items, available_items, use_abundances, recipe_amount, item_count } }
+               clock_speeds: ClockSpeeds,
+               input: &ProblemInput) -> Self {
+        let building_count = if input.integer_buildings {
+            recipe_amount.keys()
+                .map(|recipe| (recipe.clone(), variables.add(variable().integer().min(0))))
+                .collect()
+        } else {
+            LinkedHashMap::new()
+        };
+
+        let recipe_used = if input.objective.uses(Criterion::Recipes) {
+            recipe_amount.keys()
+                .map(|recipe| (recipe.clone(), variables.add(variable().binary())))
+                .collect()
+        } else {
+            LinkedHashMap::new()
+        };
+
+        let scale = if input.maximize { Some(variables.add(variable().min(0))) } else { None };
+
+        let data = ProblemData {
+            target_items,
+            available_items,
+            use_abundances: input.use_abundances,
+            resource_caps: HashMap::new(),
+            resource_weights: HashMap::new(),
+            power: input.power,
+            byproducts: input.byproducts,
+            byproduct_policies: HashMap::new(),
+            max_power: input.max_power,
+            objective: input.objective.clone(),
+            clock_speeds,
+            recipe_amount,
+            item_count,
+            building_count,
+            recipe_used,
+            scale,
+            relaxation: None,
+            loosening: None,
+        };
+        Problem { variables, data }
     }
 }
 
 
 impl Problem {
-    pub(crate) fn solve(self) -> crate::error::Result<Bom> {
+    pub(crate) fn solve(self) -> crate::error::Result<crate::Bom> {
         let data = self.data;
 
         let objective = data.objective();
@@ -46,41 +128,265 @@ impl Problem {
 
         let result = variables.solve()?;
 
-        Ok(data.create_boom(result))
+        data.create_boom(result)
+    }
+
+    /// Find the largest factor that can be applied to the target ratios
+    pub(crate) fn maximal_scale(self) -> crate::error::Result<f64> {
+        let data = self.data;
+        let scale = data.scale.ok_or(Error::NotMaximizing)?;
+
+        let variables = self.variables.maximise(scale).using(default_solver);
+        let variables = data.compute_constraints().into_iter().fold(variables, |v, c| v.with(c));
+
+        let result = variables.solve()?;
+        Ok(result.value(scale))
+    }
+
+    /// Check if the problem has a solution
+    pub(crate) fn solvable(self) -> crate::error::Result<bool> {
+        match self.solve() {
+            Ok(_) => Ok(true),
+            Err(Error::ResolutionFailed(ResolutionError::Infeasible)) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// The constraints that can be removed one at a time to diagnose an infeasible problem
+    pub(crate) fn relaxations(&self) -> Vec<Relaxation> {
+        self.data.relaxations()
+    }
+
+    pub(crate) fn relaxed(mut self, relaxation: Relaxation) -> Self {
+        self.data.relaxation = Some(relaxation);
+        self
+    }
+
+    pub(crate) fn loosened(mut self, relaxation: Relaxation, extra: f64) -> Self {
+        self.data.loosening = Some((relaxation, extra));
+        self
+    }
+
+    /// Solve the problem and list the constraints that limit the solution. The value
+    /// is the objective, or the opposite of the scale when the production is maximized
+    pub(crate) fn optimum(self) -> crate::error::Result<Optimum> {
+        let data = self.data;
+        let objective = match data.scale {
+            Some(scale) => scale.into_expression().mul(-1),
+            None => data.objective(),
+        };
+
+        let variables = self.variables.minimise(objective.clone()).using(default_solver);
+        let variables = data.compute_constraints().into_iter().fold(variables, |v, c| v.with(c));
+        let result = variables.solve()?;
+
+        let usages = data.relaxations().into_iter()
+            .map(|relaxation| {
+                let usage = data.usage(&relaxation, &result);
+                (relaxation, usage)
+            })
+            .collect();
+        Ok(Optimum { value: objective.eval_with(&result), binding: data.binding_constraints(&result), usages })
+    }
+
+    pub(crate) fn with_resource_caps(mut self, resource_caps: HashMap<Item, f64>) -> Self {
+        self.data.resource_caps = resource_caps;
+        self
+    }
+
+    pub(crate) fn with_resource_weights(mut self, resource_weights: HashMap<Item, f64>) -> Self {
+        self.data.resource_weights = resource_weights;
+        self
+    }
+
+    pub(crate) fn with_byproduct_policies(mut self, byproduct_policies: HashMap<Item, ByproductPolicy>) -> Self {
+        self.data.byproduct_policies = byproduct_policies;
+        self
+    }
+
+    /// Turn the target ratios into the amounts obtained with the given factor
+    pub(crate) fn with_scale(mut self, scale: Rate) -> Self {
+        self.data.scale = None;
+        for bounds in self.data.target_items.values_mut() {
+            *bounds = bounds.scaled(scale);
+        }
+        self
+    }
+}
+
+/// The value of the objective of a solved problem, and the constraints that prevent a better one
+pub struct Optimum {
+    pub value: f64,
+    pub binding: Vec<Relaxation>,
+    //how much of each constraint the solution uses, like the amount extracted for a resource cap
+    usages: Vec<(Relaxation, f64)>,
+}
+
+impl Optimum {
+    pub fn usage(&self, relaxation: &Relaxation) -> f64 {
+        self.usages.iter().find(|(r, _)| r == relaxation).map_or(0f64, |(_, usage)| *usage)
     }
 }
 
 impl ProblemData {
-    fn objective(&self) -> Expression {
-        let mut objective = Expression::from(0);
+    /// The max amount per minute of a resource : the lowest of the limit of its nodes and the one of the input
+    fn resource_cap(&self, item: &Item) -> Option<f64> {
+        let nodes_cap = item.as_resource().and_then(nodes_capacity);
+        match (nodes_cap, self.resource_caps.get(item)) {
+            (Some(q), Some(cap)) => Some(q.min(*cap)),
+            (q, cap) => q.or_else(|| cap.cloned()),
+        }
+    }
 
-        let total: u32 = self.item_count.keys()
-            .map(|i| i.as_resource())
-            .flatten()
-            .map(|r| r.max_quantity_per_minute())
-            .flatten()
+    /// The products that are neither targets nor available items, and end up in the leftovers when not consumed
+    fn is_byproduct(&self, item: &Item) -> bool {
+        matches!(item, Item::Product(_)) && !self.target_items.contains_key(item) && !self.available_items.contains_key(item)
+    }
+
+    fn byproduct_policy(&self, item: &Item) -> ByproductPolicy {
+        self.byproduct_policies.get(item).cloned().unwrap_or(self.byproducts)
+    }
+
+    fn relaxations(&self) -> Vec<Relaxation> {
+        let mut relaxations: Vec<Relaxation> = self.item_count.keys()
+            .filter(|i| self.resource_cap(i).is_some())
+            .map(|i| Relaxation::ResourceCap(i.clone()))
+            .collect();
+        relaxations.extend(self.available_items.keys().map(|i| Relaxation::AvailableItem(i.clone())));
+        relaxations.extend(self.target_items.iter()
+            .filter(|(_, bounds)| bounds.max.is_some())
+            .map(|(i, _)| Relaxation::TargetMax(i.clone())));
+        relaxations.extend(self.item_count.keys()
+            .filter(|i| self.is_byproduct(i) && self.byproduct_policy(i) == ByproductPolicy::Forbid)
+            .map(|i| Relaxation::Byproduct(i.clone())));
+        if !self.power.is_external() {
+            relaxations.push(Relaxation::Power);
+        }
+        if self.max_power.is_some() {
+            relaxations.push(Relaxation::MaxPower);
+        }
+        relaxations
+    }
+
+    /// The resource, available item and power constraints that are reached by the solution
+    fn binding_constraints(&self, solution: &impl Solution) -> Vec<Relaxation> {
+        self.relaxations().into_iter()
+            .filter(|relaxation| match relaxation {
+                Relaxation::TargetMax(_) => false,
+                _ => is_nil(self.usage(relaxation, solution) - self.limit(relaxation) - self.extra_room(relaxation)),
+            })
+            .collect()
+    }
+
+    /// What a constraint limits in a solution : the amount extracted, the amount of an available item
+    /// consumed, the leftover of a byproduct, the amount of a target, or the power
+    fn usage(&self, relaxation: &Relaxation, solution: &impl Solution) -> f64 {
+        match relaxation {
+            Relaxation::ResourceCap(item) | Relaxation::AvailableItem(item) => -self.item_count[item].clone().eval_with(solution),
+            Relaxation::TargetMax(item) | Relaxation::Byproduct(item) => self.item_count[item].clone().eval_with(solution),
+            Relaxation::Power => self.power_expression().eval_with(solution),
+            Relaxation::MaxPower => self.consumption_expression().eval_with(solution),
+        }
+    }
+
+    /// The most a solution can use of a constraint, without extra room
+    fn limit(&self, relaxation: &Relaxation) -> f64 {
+        match relaxation {
+            Relaxation::ResourceCap(item) => self.resource_cap(item).unwrap_or(0f64),
+            Relaxation::TargetMax(item) => self.target_items.get(item).and_then(|b| b.max).map_or(0f64, |max| max.as_f64()),
+            Relaxation::AvailableItem(_) | Relaxation::Byproduct(_) => 0f64,
+            Relaxation::Power => match self.power {
+                PowerSetting::Grid(budget) => budget,
+                _ => 0f64,
+            },
+            Relaxation::MaxPower => self.max_power.unwrap_or(0f64),
+        }
+    }
+
+    fn objective(&self) -> Expression {
+        let mut objective: Expression = self.objective.criteria().iter()
+            .map(|(weight, criterion)| self.criterion_expression(*criterion).mul(*weight))
             .sum();
 
+        //the amount of recipes breaks the ties between equivalent plans
+        objective += self.recipe_amount.values().sum::<Expression>().div(1000);
+        //the cost of the penalized byproducts
         for (item, e) in &self.item_count {
-            if self.target_items.contains_key(item) {
-                continue;
+            if let ByproductPolicy::Penalize(cost) = self.byproduct_policy(item) {
+                if self.is_byproduct(item) {
+                    objective += e.clone().mul(cost);
+                }
             }
+        }
+        //prevents the solver from adding idle buildings
+        objective + self.building_count.values().sum::<Expression>().div(1000)
+    }
 
-            let available_items = self.available_items.contains_key(item);
-            let target = self.target_items.contains_key(item);
+    fn criterion_expression(&self, criterion: Criterion) -> Expression {
+        match criterion {
+            Criterion::Resources => self.resources_objective(),
+            Criterion::Buildings => self.buildings_expression(),
+            Criterion::Power => self.consumption_expression(),
+            Criterion::Waste => self.waste_expression(),
+            Criterion::Recipes => self.recipe_used.values().sum(),
+            Criterion::Shards => self.shards_expression(),
+        }
+    }
 
-            let e = e.clone().mul(total);
-            if let (Item::Resource(r), _, _) = (item, target, available_items) {
-                if let Some(mq) = r.max_quantity_per_minute() {
-                    objective -= e.div(if self.use_abundances { mq } else { 1 });
-                } else {
-                    objective -= e.div(1000000000)
-                }
-            }
+    /// The power shards of the buildings, one for each 50% of clock speed above 100%
+    fn shards_expression(&self) -> Expression {
+        let shards = |recipe: &Recipe| self.clock_speeds.of(recipe).power_shards() as f64;
+        if !self.building_count.is_empty() {
+            return self.building_count.iter()
+                .map(|(recipe, count)| count.into_expression().mul(shards(recipe)))
+                .sum();
         }
+        self.recipe_amount.iter()
+            .map(|(recipe, amount)| amount.into_expression().mul(self.clock_speeds.building_count(recipe, 1f64) * shards(recipe)))
+            .sum()
+    }
 
-        for (_, amount) in &self.recipe_amount {
-            objective += amount
+    /// The number of buildings, fractional unless the building counts are integer variables
+    fn buildings_expression(&self) -> Expression {
+        if !self.building_count.is_empty() {
+            return self.building_count.values().sum();
+        }
+        self.recipe_amount.iter()
+            .map(|(recipe, amount)| amount.into_expression().mul(self.clock_speeds.building_count(recipe, 1f64)))
+            .sum()
+    }
+
+    /// The amount per minute of the products that are not targeted
+    fn waste_expression(&self) -> Expression {
+        self.item_count.iter()
+            .filter(|(item, _)| matches!(item, Item::Product(_)) && !self.target_items.contains_key(item))
+            .map(|(_, e)| e.clone())
+            .sum()
+    }
+
+    /// The units of resources extracted. With the abundances, a unit weighs the extraction capacity
+    /// of the nodes of all the resources over the one of its own nodes, so the rare resources cost more
+    fn resources_objective(&self) -> Expression {
+        let total: f64 = self.item_count.keys()
+            .filter_map(|i| i.as_resource())
+            .filter_map(nodes_capacity)
+            .sum();
+
+        let mut objective = Expression::from(0);
+        for (item, e) in &self.item_count {
+            let resource = match item.as_resource() {
+                Some(r) if !self.target_items.contains_key(item) => r,
+                _ => continue,
+            };
+            let capacity = nodes_capacity(resource).filter(|mq| !is_nil(*mq));
+            let weight = match (self.resource_weights.get(item), capacity) {
+                (Some(weight), _) => *weight,
+                (None, Some(mq)) if self.use_abundances => total / mq,
+                (None, Some(_)) => 1f64,
+                //the resources extracted anywhere, like water, are almost free
+                (None, None) => total / 1e9,
+            };
+            objective -= e.clone().mul(weight);
         }
         objective
     }
@@ -91,52 +397,817 @@ impl ProblemData {
             let target = self.target_items.get(item).cloned();
             let available = self.available_items.get(item).cloned();
             match item {
-                Item::Resource(r) => {
+                Item::Resource(_) => {
                     constraints.push(e.clone().leq(0));
-                    if let Some(q) = r.max_quantity_per_minute() {
-                        constraints.push(e.clone().geq(-(q as f64)));
+                    let cap = Relaxation::ResourceCap(item.clone());
+                    if let Some(q) = self.resource_cap(item).filter(|_| !self.is_relaxed(&cap)) {
+                        constraints.push(e.clone().geq(-q - self.extra_room(&cap)));
                     }
                 }
-                Item::Product(_) => {
-                    let constraint = match (target, available) {
-                        (Some(amount), _) => e.clone().eq(amount),
-                        _ => e.clone().geq(0)
-                    };
-                    constraints.push(constraint);
+                Item::Product(_) => match (target, available) {
+                    (Some(bounds), _) if self.is_relaxed(&Relaxation::TargetMax(item.clone())) => {
+                        constraints.extend(self.target_constraints(e, Bounds { min: bounds.min, max: None }))
+                    }
+                    (Some(bounds), _) => constraints.extend(self.target_constraints(e, bounds)),
+                    (None, Some(_)) if self.is_relaxed(&Relaxation::AvailableItem(item.clone())) => {}
+                    (None, Some(_)) => constraints.push(e.clone().geq(-self.extra_room(&Relaxation::AvailableItem(item.clone())))),
+                    _ => {
+                        constraints.push(e.clone().geq(0));
+                        let forbidden = Relaxation::Byproduct(item.clone());
+                        if self.byproduct_policy(item) == ByproductPolicy::Forbid && !self.is_relaxed(&forbidden) {
+                            constraints.push(e.clone().leq(self.extra_room(&forbidden)));
+                        }
+                    }
                 }
             }
         }
+
+        match self.power {
+            _ if self.is_relaxed(&Relaxation::Power) => {}
+            PowerSetting::External => {}
+            PowerSetting::SelfPowered => constraints.push(self.power_expression().leq(self.extra_room(&Relaxation::Power))),
+            PowerSetting::Grid(budget) => constraints.push(self.power_expression().leq(budget + self.extra_room(&Relaxation::Power))),
+        }
+        if let Some(max_power) = self.max_power.filter(|_| !self.is_relaxed(&Relaxation::MaxPower)) {
+            constraints.push(self.consumption_expression().leq(max_power + self.extra_room(&Relaxation::MaxPower)));
+        }
+
+        //a recipe can only be executed if it is marked as used
+        for (recipe, used) in &self.recipe_used {
+            let amount = self.recipe_amount[recipe];
+            constraints.push(amount.into_expression().leq(used.into_expression().mul(MAX_RECIPE_AMOUNT)));
+        }
+
+        //the buildings must be able to execute the recipes at their clock speed, the BoM
+        //underclocks them to the exact amount
+        for (recipe, count) in &self.building_count {
+            let amount = self.recipe_amount[recipe];
+            let capacity = 1f64 / self.clock_speeds.building_count(recipe, 1f64);
+            constraints.push(count.into_expression().mul(capacity).geq(amount));
+        }
         constraints
     }
+
+    fn is_relaxed(&self, relaxation: &Relaxation) -> bool {
+        self.relaxation.as_ref() == Some(relaxation)
+    }
+
+    fn extra_room(&self, relaxation: &Relaxation) -> f64 {
+        match &self.loosening {
+            Some((loosened, extra)) if loosened == relaxation => *extra,
+            _ => 0f64,
+        }
+    }
+
+    /// Keep the amount of a target within its bounds, multiplied by the scale when the production is maximized
+    fn target_constraints(&self, e: &Expression, bounds: Bounds) -> Vec<Constraint> {
+        let bound = |amount: Rate| match self.scale {
+            Some(scale) => scale.into_expression().mul(amount.as_f64()),
+            None => Expression::from(amount.as_f64()),
+        };
+
+        if bounds.is_exact() {
+            return vec![e.clone().eq(bound(bounds.min))];
+        }
+        let mut constraints = vec![e.clone().geq(bound(bounds.min))];
+        if let Some(max) = bounds.max {
+            constraints.push(e.clone().leq(bound(max)));
+        }
+        constraints
+    }
+
+    /// The power consumed by the buildings and the extractors in MW, without the power produced
+    /// by the generators
+    fn consumption_expression(&self) -> Expression {
+        self.recipe_amount.iter()
+            .filter(|(recipe, _)| recipe.building().power_usage() > 0)
+            .map(|(recipe, amount)| amount.into_expression().mul(self.clock_speeds.recipe_power(recipe)))
+            .sum::<Expression>() + self.extraction_expression()
+    }
+
+    /// The net power consumed by the buildings and the extractors in MW. Generators have
+    /// a negative power usage and reduce the consumption
+    fn power_expression(&self) -> Expression {
+        self.recipe_amount.iter()
+            .map(|(recipe, amount)| amount.into_expression().mul(self.clock_speeds.recipe_power(recipe)))
+            .sum::<Expression>() + self.extraction_expression()
+    }
+
+    /// The power used by the extractors in MW, proportional to the amount of each resource
+    /// extracted (the opposite of its balance)
+    fn extraction_expression(&self) -> Expression {
+        self.item_count.iter()
+            .filter_map(|(item, e)| item.as_resource().map(|r| e.clone().mul(-r.extraction_power())))
+            .sum()
+    }
 }
 
 
 impl ProblemData {
-    fn create_boom(self, solution: LpSolution) -> Bom {
-        let recipes = self.recipe_amount.into_iter()
-            .map(|(recipe, variable)| (recipe, solution.value(variable)))
-            .filter(|(_, a)| !is_nil(*a))
+    /// Snap the amounts of the solution to exact fractions, then balance the items exactly
+    /// from the recipes instead of evaluating the item expressions with floats
+    fn create_boom(self, solution: impl Solution) -> crate::error::Result<Bom> {
+        let objective = self.objective().eval_with(&solution);
+        let byproduct_policies: HashMap<Item, ByproductPolicy> = self.item_count.keys()
+            .filter(|item| self.is_byproduct(item))
+            .map(|item| (item.clone(), self.byproduct_policy(item)))
+            .filter(|(_, policy)| !policy.is_allow())
+            .collect();
+
+        let building_counts = self.building_count.into_iter()
+            .map(|(recipe, variable)| (recipe, solution.value(variable).round() as u32))
+            .collect();
+
+        let recipes: HashMap<Recipe, Rate> = self.recipe_amount.into_iter()
+            .map(|(recipe, variable)| (recipe, Rate::snapped(solution.value(variable))))
+            .filter(|(_, a)| !a.is_zero())
             .collect();
 
+        let available_items: HashMap<Item, Rate> = self.available_items.iter()
+            .map(|(item, amount)| (item.clone(), Rate::snapped(*amount)))
+            .collect();
+
+        //the fractions of a solution which is not made of simple ones can overflow
+        let mut balances: HashMap<Item, Rate> = self.item_count.keys().map(|item| (item.clone(), Rate::default())).collect();
+        let mut add = |item: &Item, amount: Option<Rate>| {
+            let balance = balances.entry(item.clone()).or_default();
+            *balance = amount.and_then(|a| balance.checked_add(a))
+                .ok_or_else(|| Error::RateOverflow(item.id().to_string()))?;
+            Ok::<(), Error>(())
+        };
+        for (item, amount) in &available_items {
+            add(item, Some(*amount))?;
+        }
+        for (recipe, amount) in &recipes {
+            for input in recipe.inputs() {
+                add(input.item(), amount.checked_mul(Rate::per_minute(-(input.quantity() as i64))))?;
+            }
+            for output in recipe.outputs() {
+                add(output.item(), amount.checked_mul(Rate::per_minute(output.quantity() as i64)))?;
+            }
+        }
+
 
         let mut targets = HashMap::new();
         let mut requirements = HashMap::new();
         let mut leftovers = HashMap::new();
+        let mut sunk = HashMap::new();
 
-        for (item, expression) in self.item_count {
-            let amount = expression.eval_with(&solution);
-            if is_nil(amount) {
+        //the recipes have been moved out of self, only its relaxation is borrowed
+        let relaxed = |relaxation: Relaxation| self.relaxation.as_ref() == Some(&relaxation);
+        for (item, amount) in balances {
+            //the snapped amounts must still be a plan : no missing product, and the targets within their bounds.
+            //The comparisons are exact, any residual left by the snapping is kept
+            let balanced = match (&item, self.target_items.get(&item)) {
+                (Item::Resource(_), _) => true,
+                (Item::Product(_), Some(bounds)) if relaxed(Relaxation::TargetMax(item.clone())) => Bounds { max: None, ..*bounds }.contains(amount),
+                (Item::Product(_), Some(bounds)) => bounds.contains(amount),
+                _ if relaxed(Relaxation::AvailableItem(item.clone())) => true,
+                _ => amount >= Rate::default(),
+            };
+            if !balanced {
+                return Err(Error::UnbalancedSolution(item.id().to_string()));
+            }
+            if amount.is_zero() {
                 continue;
             }
             let target = self.target_items.contains_key(&item);
+            //the items without sink points, like the fluids, can not be sunk
+            let sinkable = byproduct_policies.get(&item) == Some(&ByproductPolicy::Sink) && item.metadata().sink_points().is_some();
             match (&item, target) {
                 (Item::Resource(_), _) => requirements.insert(item, -amount),
                 (Item::Product(_), true) => targets.insert(item, amount),
+                (Item::Product(_), false) if sinkable => sunk.insert(item, amount),
                 (Item::Product(_), false) => leftovers.insert(item, amount)
             };
         }
 
 
-        Bom::new(targets, self.available_items, requirements, leftovers, recipes)
+        Ok(Bom::new(targets, available_items, requirements, leftovers, recipes, self.clock_speeds, building_counts)
+            .with_byproducts(sunk, byproduct_policies)
+            .with_objective(objective))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use good_lp::{Solution, Variable};
+    use maplit::hashmap;
+
+    use crate::diagnosis::diagnose;
+    use crate::error::Error;
+    use crate::factory::Factory;
+    use crate::fixtures::{amounts, fixture_book, plates, recipe_amounts, solve};
+    use crate::model::book::FilterableBook;
+    use crate::model::amount_format::AmountFormat;
+    use crate::model::bom_printer::BomPrinter;
+    use crate::problem::{Problem, ProblemData, Relaxation};
+    use crate::model::item::Item;
+    use crate::model::name_format::NameFormat;
+    use crate::model::rate::Rate;
+    use crate::problem_input::{Bounds, ByproductPolicy, Criterion, Objective, PowerSetting, ProblemInput, TargetAmount};
+    use crate::recipe_filter::RecipeFilter;
+
+    struct Values(HashMap<Variable, f64>);
+
+    impl Solution for Values {
+        fn value(&self, variable: Variable) -> f64 {
+            self.0.get(&variable).cloned().unwrap_or(0f64)
+        }
+    }
+
+    #[test]
+    fn power_balance() {
+        //20 plates per minute need one constructor (4 MW) and one smelter (4 MW) for the 30 ingots, and their
+        //ore 5/2 MW of miners. A coal generator burning 15 coal per minute provides 75 MW, minus the 5/4 MW of
+        //the miners of its coal and the 15/2 MW of the extractors of its 45 water
+        let input = ProblemInput { power: PowerSetting::SelfPowered, ..plates("20") };
+        let data = fixture_problem(&input).data;
+        let values = fixture_values(&data, &[("iron_ingot", 30f64), ("iron_plate", 10f64), ("coal_power", 15f64)]);
+        assert!((data.power_expression().eval_with(&values) - (8f64 + 2.5f64 - 75f64 + 1.25f64 + 7.5f64)).abs() < 1e-6);
+        assert_eq!(data.compute_constraints().len(), nb_item_constraints(&data) + 1);
+
+        let data = fixture_problem(&ProblemInput { power: PowerSetting::External, ..input }).data;
+        assert_eq!(data.compute_constraints().len(), nb_item_constraints(&data));
+    }
+
+    #[test]
+    #[ignore = "needs the cbc solver"]
+    fn power_balance_solved() {
+        //the refinery uses 40 MW and the miners of its 40/3 ore 10/9 MW, each execution of the
+        //generator gives 5 MW minus 7/12 MW for the extraction of its coal and water. Without the
+        //abundances, the scarce coal costs as much as the ore
+        let input = ProblemInput { power: PowerSetting::SelfPowered, use_abundances: false, ..plates("20") };
+        let bom = solve(&input).unwrap();
+        assert_eq!(recipe_amounts(&bom), vec!["coal_power: 1480/159", "pure_iron_plate: 20/3"]);
+        assert_eq!(amounts(&bom.requirements), vec!["coal: 1480/159", "iron_ore: 40/3", "water: 1480/53"]);
+
+        //the grid provides 30 MW
+        let input = ProblemInput { power: PowerSetting::Grid(30f64), ..input };
+        let bom = solve(&input).unwrap();
+        assert_eq!(recipe_amounts(&bom), vec!["coal_power: 400/159", "pure_iron_plate: 20/3"]);
+    }
+
+    #[test]
+    fn power_objective_and_budget() {
+        let input = ProblemInput {
+            power: PowerSetting::SelfPowered,
+            max_power: Some(100f64),
+            objective: Objective::single(Criterion::Power),
+            ..plates("20")
+        };
+        let data = fixture_problem(&input).data;
+        let values = fixture_values(&data, &[("iron_ingot", 30f64), ("iron_plate", 10f64), ("coal_power", 15f64)]);
+
+        //the generator does not reduce the consumption of the buildings and the extractors
+        assert!((data.consumption_expression().eval_with(&values) - (8f64 + 2.5f64 + 1.25f64 + 7.5f64)).abs() < 1e-6);
+        assert!((data.objective().eval_with(&values) - (19.25f64 + 55f64 / 1000f64)).abs() < 1e-6);
+        assert_eq!(data.compute_constraints().len(), nb_item_constraints(&data) + 2);
+    }
+
+    #[test]
+    #[ignore = "needs the cbc solver"]
+    fn power_objective_and_budget_solved() {
+        //the ingots use the least power : 8 MW, and 5/2 MW for the miners of their 30 ore,
+        //plus the 40 executions breaking the ties
+        let input = ProblemInput { objective: Objective::single(Criterion::Power), max_power: Some(11f64), ..plates("20") };
+        let bom = solve(&input).unwrap();
+        assert_eq!(recipe_amounts(&bom), vec!["iron_ingot: 30", "iron_plate: 10"]);
+        assert!((bom.objective - (10.5f64 + 40f64 / 1000f64)).abs() < 1e-3);
+        let input = ProblemInput { max_power: Some(10f64), ..input };
+        assert!(matches!(solve(&input), Err(Error::Infeasible(_))));
+
+        //the generators are not deducted : the refinery alone, its generators and their extractors
+        //would use 46.5 MW, so some plates come from ingots
+        let input = ProblemInput { power: PowerSetting::SelfPowered, max_power: Some(45f64), ..plates("20") };
+        let bom = solve(&input).unwrap();
+        assert!(bom.recipes.keys().any(|r| r.id() == "iron_ingot"));
+    }
+
+    #[test]
+    fn recipe_clock_speeds() {
+        //the 4/3 refineries are 8/3 at 50%, using 30 MW × 0.5^1.32 each
+        let input = ProblemInput { recipe_clock_speeds: hashmap! {"pure_iron_plate".to_string() => 50f64}, ..plates("20") };
+        let data = fixture_problem(&input).data;
+        let values = fixture_values(&data, &[("pure_iron_plate", 20f64 / 3f64)]);
+        assert!((data.buildings_expression().eval_with(&values) - 8f64 / 3f64).abs() < 1e-9);
+        assert!((data.consumption_expression().eval_with(&values) - (8f64 / 3f64 * 30f64 * 0.5f64.powf(1.321928) + 10f64 / 9f64)).abs() < 1e-6);
+
+        let input = ProblemInput { recipe_clock_speeds: hashmap! {"pure_iron_plates".to_string() => 50f64}, ..plates("20") };
+        assert!(matches!(try_fixture_problem(&input), Err(Error::UnknownRecipe(recipe)) if recipe == "pure_iron_plates"));
+    }
+
+    #[test]
+    #[ignore = "needs the cbc solver"]
+    fn recipe_clock_speeds_solved() {
+        let input = ProblemInput { recipe_clock_speeds: hashmap! {"pure_iron_plate".to_string() => 50f64}, ..plates("20") };
+        assert_eq!(solve(&input).unwrap().building_count(), 3);
+    }
+
+    #[test]
+    fn power_shards_objective() {
+        //the 8/9 refineries at 150% need a shard each, the 8/15 at 250% need three
+        let shards = |percent: f64| {
+            let input = ProblemInput { recipe_clock_speeds: hashmap! {"pure_iron_plate".to_string() => percent}, ..plates("20") };
+            let data = fixture_problem(&input).data;
+            let values = fixture_values(&data, &[("pure_iron_plate", 20f64 / 3f64)]);
+            data.criterion_expression(Criterion::Shards).eval_with(&values)
+        };
+        assert!((shards(150f64) - 8f64 / 9f64).abs() < 1e-9);
+        assert!((shards(250f64) - 8f64 / 5f64).abs() < 1e-9);
+        assert!(shards(100f64).abs() < 1e-9);
+        assert_eq!("shards".parse::<Objective>().unwrap(), Objective::single(Criterion::Shards));
+    }
+
+    #[test]
+    fn integer_buildings() {
+        let input = ProblemInput { integer_buildings: true, ..plates("20") };
+        let data = fixture_problem(&input).data;
+
+        //each recipe has a building count that must be able to execute it
+        assert_eq!(data.building_count.len(), data.recipe_amount.len());
+        assert_eq!(data.compute_constraints().len(), nb_item_constraints(&data) + data.recipe_amount.len());
+
+        //one building more adds its tie-breaking cost to the objective
+        let values = fixture_values(&data, &[("iron_ingot", 30f64), ("iron_plate", 10f64)]);
+        let mut with_building = Values(values.0.clone());
+        with_building.0.insert(data.building_count.values().next().cloned().unwrap(), 1f64);
+        assert!((data.objective().eval_with(&with_building) - data.objective().eval_with(&values) - 1f64 / 1000f64).abs() < 1e-9);
+    }
+
+    #[test]
+    #[ignore = "needs the cbc solver"]
+    fn integer_buildings_solved() {
+        let buildings = |target: &str| {
+            let bom = solve(&ProblemInput { integer_buildings: true, ..plates(target) }).unwrap();
+            let mut buildings = String::new();
+            BomPrinter::with_writer(&mut buildings, AmountFormat::F64, NameFormat::Ids).display_buildings(&bom.buildings).unwrap();
+            buildings.lines().nth(1).unwrap().trim().to_string()
+        };
+
+        //the 4/3 refineries are rounded up to 2, running at 2/3 of their speed
+        assert!(buildings("20").starts_with("2 -      refinery @ 66.67%"));
+        //10 executions need exactly 2 refineries
+        assert!(buildings("30").starts_with("2 -      refinery @ 100%"));
+    }
+
+    //one constraint per item, and one more for the resources with a cap
+    fn nb_item_constraints(data: &ProblemData) -> usize {
+        let nb_capped_resources = data.item_count.keys()
+            .filter(|i| data.resource_cap(i).is_some())
+            .count();
+        data.item_count.len() + nb_capped_resources
+    }
+
+    fn fixture_problem(input: &ProblemInput) -> Problem {
+        try_fixture_problem(input).unwrap()
+    }
+
+    fn try_fixture_problem(input: &ProblemInput) -> crate::error::Result<Problem> {
+        let book = fixture_book();
+        let book = book.filter(input.filter())?;
+        Factory::create_problem(input, &book)
+    }
+
+    fn fixture_item(problem: &Problem, id: &str) -> Item {
+        fixture_item_of(&problem.data, id)
+    }
+
+    fn fixture_item_of(data: &ProblemData, id: &str) -> Item {
+        data.item_count.keys().find(|i| i.id() == id).cloned().unwrap()
+    }
+
+    //the recipes with a non-nil amount are also marked as used
+    fn fixture_values(data: &ProblemData, amounts: &[(&str, f64)]) -> Values {
+        let mut values = HashMap::new();
+        for (id, amount) in amounts {
+            let (recipe, variable) = data.recipe_amount.iter().find(|(r, _)| r.id() == *id).unwrap();
+            values.insert(*variable, *amount);
+            if let Some(used) = data.recipe_used.get(recipe) {
+                values.insert(*used, 1f64);
+            }
+        }
+        Values(values)
+    }
+
+    //returns the objective of the plan using ingots and the one of the plan using the refinery
+    fn evaluate_fixture(objective: &str) -> (f64, f64) {
+        let input = ProblemInput { objective: objective.parse().unwrap(), use_abundances: false, ..plates("20") };
+        let data = fixture_problem(&input).data;
+        let with_ingots = fixture_values(&data, &[("iron_ingot", 30f64), ("iron_plate", 10f64)]);
+        let with_refinery = fixture_values(&data, &[("pure_iron_plate", 20f64 / 3f64)]);
+        (data.objective().eval_with(&with_ingots), data.objective().eval_with(&with_refinery))
+    }
+
+    fn used_recipes(objective: &str) -> Vec<String> {
+        let bom = solve(&ProblemInput { objective: objective.parse().unwrap(), ..plates("20") }).unwrap();
+        let mut ids: Vec<String> = bom.recipes.keys().map(|r| r.id().to_string()).collect();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn objective_criteria() {
+        //the 40 executions of the ingots and the 20/3 of the refinery break the ties
+        let close = |(ingots, refinery): (f64, f64), expected: (f64, f64)| (ingots - expected.0 - 0.04f64).abs() < 1e-6
+            && (refinery - expected.1 - 0.02f64 / 3f64).abs() < 1e-6;
+
+        //30 ore against 40/3
+        assert!(close(evaluate_fixture("resources"), (30f64, 40f64 / 3f64)));
+        //2 buildings against 4/3
+        assert!(close(evaluate_fixture("buildings"), (2f64, 4f64 / 3f64)));
+        //8 MW against 40 MW, plus the miners of the ore
+        assert!(close(evaluate_fixture("power"), (10.5f64, 40f64 + 10f64 / 9f64)));
+        //no leftover against 20/3 slag
+        assert!(close(evaluate_fixture("waste"), (0f64, 20f64 / 3f64)));
+        //two recipes against one
+        assert!(close(evaluate_fixture("recipes"), (2f64, 1f64)));
+        assert!(close(evaluate_fixture("2*waste+power"), (10.5f64, 40f64 + 10f64 / 9f64 + 40f64 / 3f64)));
+
+        //a recipe is only executed when it is marked as used
+        let constraints = |objective: &str| {
+            let input = ProblemInput { objective: objective.parse().unwrap(), ..plates("20") };
+            fixture_problem(&input).data.compute_constraints().len()
+        };
+        assert_eq!(constraints("recipes"), constraints("power") + fixture_book().recipes().len());
+    }
+
+    #[test]
+    #[ignore = "needs the cbc solver"]
+    fn objective_criteria_solved() {
+        //the refinery uses less ore and 4/3 buildings instead of 2 in a single recipe,
+        //the ingots use 8 MW instead of 40 and leave no slag
+        assert_eq!(used_recipes("resources"), vec!["pure_iron_plate"]);
+        assert_eq!(used_recipes("buildings"), vec!["pure_iron_plate"]);
+        assert_eq!(used_recipes("recipes"), vec!["pure_iron_plate"]);
+        assert_eq!(used_recipes("power"), vec!["iron_ingot", "iron_plate"]);
+        assert_eq!(used_recipes("waste"), vec!["iron_ingot", "iron_plate"]);
+
+        //the weights decide between the buildings and the power
+        assert_eq!(used_recipes("20*buildings+power"), vec!["iron_ingot", "iron_plate"]);
+        assert_eq!(used_recipes("100*buildings+power"), vec!["pure_iron_plate"]);
+    }
+
+    #[test]
+    fn objective_parsing() {
+        let objective: Objective = "resources + 0.5*power".parse().unwrap();
+        assert_eq!(objective.criteria(), &[(1f64, Criterion::Resources), (0.5f64, Criterion::Power)]);
+        assert_eq!(objective.to_string(), "resources+0.5*power");
+        assert!("speed".parse::<Objective>().is_err());
+        assert!("-1*power".parse::<Objective>().is_err());
+    }
+
+    #[test]
+    fn maximized_production() {
+        let input = ProblemInput {
+            target_items: hashmap! {"iron_plate".to_string() => "4".parse().unwrap(), "slag".to_string() => "1".parse().unwrap()},
+            maximize: true,
+            ..Default::default()
+        };
+        let data = fixture_problem(&input).data;
+        let scale = data.scale.unwrap();
+
+        //the refinery makes plates and slag in a 3:1 ratio, so the 4:1 ratio needs plates from ingots
+        let mut values = fixture_values(&data, &[("pure_iron_plate", 10f64), ("iron_ingot", 15f64), ("iron_plate", 5f64)]);
+        values.0.insert(scale, 10f64);
+        let plates = &data.item_count[&fixture_item_of(&data, "iron_plate")];
+        assert!((plates.eval_with(&values) - 40f64).abs() < 1e-6);
+        assert_eq!(data.compute_constraints().len(), nb_item_constraints(&data));
+
+        //the scale found by the solver turns the ratios into amounts
+        let data = fixture_problem(&input).with_scale(Rate::per_minute(10)).data;
+        assert!(data.scale.is_none());
+        assert_eq!(data.target_items[&fixture_item_of(&data, "iron_plate")], Bounds { min: Rate::per_minute(40), max: Some(Rate::per_minute(40)) });
+    }
+
+    #[test]
+    #[ignore = "needs the cbc solver"]
+    fn maximized_production_solved() {
+        let input = ProblemInput {
+            target_items: hashmap! {"iron_plate".to_string() => "4".parse().unwrap(), "slag".to_string() => "1".parse().unwrap()},
+            maximize: true,
+            ..Default::default()
+        };
+
+        //the refinery makes plates and slag in a 3:1 ratio, the missing plates come from ingots :
+        //7 ore for 8 plates and 2 slag, so the 1680 ore of the nodes give 1920 plates and 480 slag
+        let bom = solve(&input).unwrap();
+        assert_eq!(amounts(&bom.targets), vec!["iron_plate: 1920", "slag: 480"]);
+        assert_eq!(amounts(&bom.requirements), vec!["iron_ore: 1680"]);
+
+        //the solver rounds the largest scale, 2520/11, above its exact value
+        let bom = solve(&ProblemInput { maximize: true, ..plates("11") }).unwrap();
+        assert_eq!(amounts(&bom.targets), vec!["iron_plate: 2520"]);
+    }
+
+    #[test]
+    fn target_ranges() {
+        let input = ProblemInput {
+            target_items: hashmap! {"iron_plate".to_string() => "10..15".parse().unwrap(), "slag".to_string() => "5+".parse().unwrap()},
+            ..Default::default()
+        };
+        let data = fixture_problem(&input).data;
+
+        //the plates have a min and a max, the slag only a min like the other products
+        assert_eq!(data.compute_constraints().len(), nb_item_constraints(&data) + 1);
+
+        assert_eq!("12".parse::<TargetAmount>().unwrap(), TargetAmount::Exact(Rate::per_minute(12)));
+        assert_eq!("10..15".parse::<TargetAmount>().unwrap(), TargetAmount::Range { min: Rate::per_minute(10), max: Some(Rate::per_minute(15)) });
+        assert_eq!("10+".parse::<TargetAmount>().unwrap(), TargetAmount::Range { min: Rate::per_minute(10), max: None });
+        assert!("15..10".parse::<TargetAmount>().is_err());
+        let json: HashMap<String, TargetAmount> = serde_json::from_str(r#"{"a": 3, "b": {"min": 1, "max": 2}, "c": {"min": 4}}"#).unwrap();
+        assert_eq!(json["a"], TargetAmount::Exact(Rate::per_minute(3)));
+        assert_eq!(json["b"], TargetAmount::Range { min: Rate::per_minute(1), max: Some(Rate::per_minute(2)) });
+        assert_eq!(json["c"], TargetAmount::Range { min: Rate::per_minute(4), max: None });
+        assert!(serde_json::from_str::<TargetAmount>(r#"{"min": 10, "max": 5}"#).is_err());
+    }
+
+    #[test]
+    #[ignore = "needs the cbc solver"]
+    fn target_ranges_solved() {
+        //the refinery makes the plates with the least ore, and the 5 slag come with 15 plates
+        let input = ProblemInput {
+            target_items: hashmap! {"iron_plate".to_string() => "10..15".parse().unwrap(), "slag".to_string() => "5+".parse().unwrap()},
+            ..Default::default()
+        };
+        let bom = solve(&input).unwrap();
+        assert_eq!(amounts(&bom.targets), vec!["iron_plate: 15", "slag: 5"]);
+
+        //without a slag target, the fewest plates are made
+        assert_eq!(amounts(&solve(&plates("10..15")).unwrap().targets), vec!["iron_plate: 10"]);
+        assert_eq!(amounts(&solve(&plates("12+")).unwrap().targets), vec!["iron_plate: 12"]);
+    }
+
+    #[test]
+    fn unreachable_targets() {
+        //no recipe kept by the filter makes plates, they are still constrained so that the problem is infeasible
+        let filter = RecipeFilter::AllOf(vec![RecipeFilter::NotNamed("iron_plate".to_string()), RecipeFilter::NotNamed("pure_iron_plate".to_string())]);
+        let input = ProblemInput { filter, ..plates("20") };
+        let full_book = fixture_book();
+        let book = full_book.filter(input.filter()).unwrap();
+        let data = Factory::create_problem(&input, &book).unwrap().data;
+        assert!(data.item_count.keys().any(|i| i.id() == "iron_plate"));
+        assert_eq!(data.compute_constraints().len(), nb_item_constraints(&data));
+
+        let diagnosis = diagnose(&input, &full_book, &book).unwrap();
+        assert!(diagnosis.to_string().contains("'iron_plate' can not be produced with the recipes kept by the filters"));
+    }
+
+    #[test]
+    #[ignore = "needs the cbc solver"]
+    fn unreachable_targets_solved() {
+        let filter = RecipeFilter::AllOf(vec![RecipeFilter::NotNamed("iron_plate".to_string()), RecipeFilter::NotNamed("pure_iron_plate".to_string())]);
+        let unreachable = |result: crate::error::Result<crate::Bom>| matches!(result,
+            Err(Error::Infeasible(d)) if d.to_string().contains("'iron_plate' can not be produced with the recipes kept by the filters"));
+        assert!(unreachable(solve(&ProblemInput { filter: filter.clone(), ..plates("20") })));
+        assert!(unreachable(solve(&ProblemInput { filter, maximize: true, ..plates("1") })));
+    }
+
+    #[test]
+    fn relaxed_constraints() {
+        let input = ProblemInput {
+            available_items: hashmap! {"iron_ingot".to_string() => Rate::per_minute(5)},
+            max_power: Some(10f64),
+            ..plates("10..15")
+        };
+        let problem = fixture_problem(&input);
+        let relaxations = problem.relaxations();
+        let nb_constraints = problem.data.compute_constraints().len();
+
+        //the ore and the coal are limited by their nodes, the water is not
+        assert_eq!(relaxations.len(), 5);
+        for relaxation in [
+            Relaxation::ResourceCap(fixture_item(&problem, "iron_ore")),
+            Relaxation::ResourceCap(fixture_item(&problem, "coal")),
+            Relaxation::AvailableItem(fixture_item(&problem, "iron_ingot")),
+            Relaxation::TargetMax(fixture_item(&problem, "iron_plate")),
+            Relaxation::MaxPower,
+        ] {
+            assert!(relaxations.contains(&relaxation));
+        }
+
+        for relaxation in relaxations {
+            let relaxed = fixture_problem(&input).relaxed(relaxation);
+            assert_eq!(relaxed.data.compute_constraints().len(), nb_constraints - 1);
+        }
+    }
+
+    #[test]
+    #[ignore = "needs the cbc solver"]
+    fn relaxed_constraints_solved() {
+        //the refinery needs 40/3 ore for 20 plates, more than the cap
+        let input = ProblemInput {
+            resource_caps: hashmap! {"iron_ore".to_string() => Rate::per_minute(10)},
+            max_power: Some(100f64),
+            ..plates("20")
+        };
+        let problem = fixture_problem(&input);
+        let iron_ore = fixture_item(&problem, "iron_ore");
+        assert!(!problem.solvable().unwrap());
+        assert!(fixture_problem(&input).relaxed(Relaxation::ResourceCap(iron_ore)).solvable().unwrap());
+        assert!(!fixture_problem(&input).relaxed(Relaxation::MaxPower).solvable().unwrap());
+    }
+
+    #[test]
+    fn binding_constraints() {
+        //the available ingots replace some ore, the max power is what the plan consumes
+        let input = ProblemInput {
+            available_items: hashmap! {"iron_ingot".to_string() => Rate::per_minute(5)},
+            ..plates("20")
+        };
+        let data = fixture_problem(&input).data;
+        let values = fixture_values(&data, &[("iron_ingot", 25f64), ("iron_plate", 10f64)]);
+        let consumption = data.consumption_expression().eval_with(&values);
+        let input = ProblemInput { max_power: Some(consumption), ..input };
+        let data = fixture_problem(&input).data;
+        let iron_ingot = fixture_item_of(&data, "iron_ingot");
+        assert_eq!(data.binding_constraints(&values), vec![Relaxation::AvailableItem(iron_ingot.clone()), Relaxation::MaxPower]);
+
+        //with some extra room, the max power is no longer binding
+        let data = fixture_problem(&input).loosened(Relaxation::MaxPower, 1f64).data;
+        assert_eq!(data.binding_constraints(&values), vec![Relaxation::AvailableItem(iron_ingot)]);
+    }
+
+    #[test]
+    #[ignore = "needs the cbc solver"]
+    fn binding_constraints_solved() {
+        //the available ingots replace some ore, and the refinery can not make all the other
+        //plates without going over the max power
+        let input = ProblemInput {
+            available_items: hashmap! {"iron_ingot".to_string() => Rate::per_minute(5)},
+            max_power: Some(20f64),
+            ..plates("20")
+        };
+        let problem = fixture_problem(&input);
+        let iron_ingot = fixture_item(&problem, "iron_ingot");
+        assert_eq!(problem.optimum().unwrap().binding, vec![Relaxation::AvailableItem(iron_ingot), Relaxation::MaxPower]);
+
+        //with a higher max power, only the ingots are binding
+        let input = ProblemInput { max_power: Some(100f64), ..input };
+        let problem = fixture_problem(&input);
+        let iron_ingot = fixture_item(&problem, "iron_ingot");
+        assert_eq!(problem.optimum().unwrap().binding, vec![Relaxation::AvailableItem(iron_ingot)]);
+    }
+
+    #[test]
+    fn resource_caps_and_weights() {
+        //only 20 ore can be extracted, which the plan with 12 ingots uses up
+        let input = ProblemInput {
+            objective: Objective::single(Criterion::Power),
+            resource_caps: hashmap! {"iron_ore".to_string() => Rate::per_minute(20)},
+            ..plates("20")
+        };
+        let data = fixture_problem(&input).data;
+        let iron_ore = fixture_item_of(&data, "iron_ore");
+        assert_eq!(data.resource_cap(&iron_ore), Some(20f64));
+        let values = fixture_values(&data, &[("iron_ingot", 12f64), ("iron_plate", 4f64), ("pure_iron_plate", 4f64)]);
+        assert!(data.binding_constraints(&values).contains(&Relaxation::ResourceCap(iron_ore)));
+
+        //the 40/3 ore of the refinery cost 10 each, the 20/3 executions break the ties
+        let input = ProblemInput { resource_weights: hashmap! {"iron_ore".to_string() => 10f64}, ..plates("20") };
+        let data = fixture_problem(&input).data;
+        let values = fixture_values(&data, &[("pure_iron_plate", 20f64 / 3f64)]);
+        assert!((data.objective().eval_with(&values) - (400f64 / 3f64 + 20f64 / 3000f64)).abs() < 1e-9);
+
+        let input = ProblemInput { resource_caps: hashmap! {"iron_plate".to_string() => Rate::per_minute(30)}, ..plates("20") };
+        assert!(matches!(try_fixture_problem(&input), Err(Error::NotAResource(item)) if item == "iron_plate"));
+    }
+
+    #[test]
+    #[ignore = "needs the cbc solver"]
+    fn resource_caps_and_weights_solved() {
+        //the ingots use the least power, but only 20 ore can be extracted so the refinery makes 12 plates
+        let input = ProblemInput {
+            objective: Objective::single(Criterion::Power),
+            resource_caps: hashmap! {"iron_ore".to_string() => Rate::per_minute(20)},
+            ..plates("20")
+        };
+        let bom = solve(&input).unwrap();
+        assert_eq!(amounts(&bom.requirements), vec!["iron_ore: 20"]);
+        assert_eq!(recipe_amounts(&bom), vec!["iron_ingot: 12", "iron_plate: 4", "pure_iron_plate: 4"]);
+
+        //the 40/3 ore of the refinery cost 10 each, the 20/3 executions break the ties
+        let input = ProblemInput { resource_weights: hashmap! {"iron_ore".to_string() => 10f64}, ..plates("20") };
+        let bom = solve(&input).unwrap();
+        assert!((bom.objective - (400f64 / 3f64 + 20f64 / 3000f64)).abs() < 1e-3);
+    }
+
+    #[test]
+    fn exact_amounts() {
+        //the solver gives floats, the BoM has the exact fractions
+        let data = fixture_problem(&plates("20")).data;
+        let values = fixture_values(&data, &[("pure_iron_plate", 20f64 / 3f64)]);
+        let bom = data.create_boom(values).unwrap();
+        assert_eq!(recipe_amounts(&bom), vec!["pure_iron_plate: 20/3"]);
+        assert_eq!(amounts(&bom.targets), vec!["iron_plate: 20"]);
+        assert_eq!(amounts(&bom.requirements), vec!["iron_ore: 40/3"]);
+        assert_eq!(amounts(&bom.leftovers), vec!["slag: 20/3"]);
+
+        //a leftover below the precision of the floats is kept
+        let data = fixture_problem(&plates("20")).data;
+        let values = fixture_values(&data, &[("iron_ingot", 30.0005f64), ("iron_plate", 10f64)]);
+        let bom = data.create_boom(values).unwrap();
+        assert_eq!(amounts(&bom.leftovers), vec!["iron_ingot: 1/2000"]);
+
+        //there is no tolerance on the bounds of the targets, the plates are just above 20
+        let data = fixture_problem(&plates("20")).data;
+        let values = fixture_values(&data, &[("pure_iron_plate", 20f64 / 3f64 + 1e-6)]);
+        assert!(matches!(data.create_boom(values), Err(Error::UnbalancedSolution(item)) if item == "iron_plate"));
+
+        //the amounts must still make the targets from the produced items
+        let data = fixture_problem(&plates("20")).data;
+        let values = fixture_values(&data, &[("iron_ingot", 29f64), ("iron_plate", 10f64)]);
+        assert!(matches!(data.create_boom(values), Err(Error::UnbalancedSolution(item)) if item == "iron_ingot"));
+        let data = fixture_problem(&plates("20")).data;
+        let values = fixture_values(&data, &[("iron_ingot", 27f64), ("iron_plate", 9f64)]);
+        assert!(matches!(data.create_boom(values), Err(Error::UnbalancedSolution(item)) if item == "iron_plate"));
+    }
+
+    #[test]
+    #[ignore = "needs the cbc solver"]
+    fn exact_amounts_solved() {
+        let bom = solve(&plates("20")).unwrap();
+        assert_eq!(recipe_amounts(&bom), vec!["pure_iron_plate: 20/3"]);
+        assert_eq!(amounts(&bom.requirements), vec!["iron_ore: 40/3"]);
+        assert_eq!(amounts(&bom.leftovers), vec!["slag: 20/3"]);
+    }
+
+    #[test]
+    fn byproduct_policies() {
+        let input = |policy: &str| ProblemInput {
+            byproduct_policies: hashmap! {"slag".to_string() => policy.parse().unwrap()},
+            ..plates("20")
+        };
+
+        //forbidding the slag adds a constraint which can be relaxed
+        let problem = fixture_problem(&input("forbid"));
+        let slag = fixture_item(&problem, "slag");
+        let nb_constraints = problem.data.compute_constraints().len();
+        assert_eq!(nb_constraints, nb_item_constraints(&problem.data) + 1);
+        assert!(problem.relaxations().contains(&Relaxation::Byproduct(slag.clone())));
+        let relaxed = problem.relaxed(Relaxation::Byproduct(slag.clone()));
+        assert_eq!(relaxed.data.compute_constraints().len(), nb_constraints - 1);
+        let problem = fixture_problem(&ProblemInput { byproducts: ByproductPolicy::Forbid, ..plates("20") });
+        assert!(problem.relaxations().contains(&Relaxation::Byproduct(slag)));
+
+        //each unit of slag costs the penalty
+        let objective = |policy: &str| {
+            let data = fixture_problem(&input(policy)).data;
+            let values = fixture_values(&data, &[("pure_iron_plate", 20f64 / 3f64)]);
+            data.objective().eval_with(&values)
+        };
+        assert!((objective("penalize:10") - objective("allow") - 200f64 / 3f64).abs() < 1e-9);
+
+        //the slag goes to the sink instead of the leftovers
+        let data = fixture_problem(&input("sink")).data;
+        let values = fixture_values(&data, &[("pure_iron_plate", 20f64 / 3f64)]);
+        let bom = data.create_boom(values).unwrap();
+        assert!(bom.leftovers.is_empty());
+        assert_eq!(amounts(&bom.sunk), vec!["slag: 20/3"]);
+        assert_eq!(bom.sink_points().to_string(), "100/3");
+
+        assert_eq!("penalize:2.5".parse::<ByproductPolicy>().unwrap(), ByproductPolicy::Penalize(2.5));
+        assert_eq!(ByproductPolicy::Penalize(1f64).to_string(), "penalize");
+        assert!("recycle".parse::<ByproductPolicy>().is_err());
+    }
+
+    #[test]
+    #[ignore = "needs the cbc solver"]
+    fn byproduct_policies_solved() {
+        let input = |policy: &str| ProblemInput {
+            byproduct_policies: hashmap! {"slag".to_string() => policy.parse().unwrap()},
+            ..plates("20")
+        };
+
+        //forbidding the slag rules out the refinery
+        let bom = solve(&input("forbid")).unwrap();
+        assert!(bom.leftovers.is_empty());
+        assert_eq!(recipe_amounts(&bom), vec!["iron_ingot: 30", "iron_plate: 10"]);
+        let bom = solve(&ProblemInput { byproducts: ByproductPolicy::Forbid, ..plates("20") }).unwrap();
+        assert!(bom.leftovers.is_empty());
+
+        //a small cost keeps the refinery, a large one makes the ingots cheaper
+        assert_eq!(amounts(&solve(&input("penalize:1")).unwrap().leftovers), vec!["slag: 20/3"]);
+        assert!(solve(&input("penalize:10")).unwrap().leftovers.is_empty());
+
+        //the slag goes to the sink instead of the leftovers
+        let bom = solve(&input("sink")).unwrap();
+        assert!(bom.leftovers.is_empty());
+        assert_eq!(amounts(&bom.sunk), vec!["slag: 20/3"]);
+        assert_eq!(bom.sink_points().to_string(), "100/3");
+
+        //the ingots need 30 ore, and the slag of the refinery is forbidden
+        let input = ProblemInput {
+            resource_caps: hashmap! {"iron_ore".to_string() => Rate::per_minute(20)},
+            byproduct_policies: hashmap! {"slag".to_string() => ByproductPolicy::Forbid},
+            ..plates("20")
+        };
+        let problem = fixture_problem(&input);
+        let slag = fixture_item(&problem, "slag");
+        assert!(!problem.solvable().unwrap());
+        assert!(fixture_problem(&input).relaxed(Relaxation::Byproduct(slag)).solvable().unwrap());
     }
 }
@@ -1,37 +1,396 @@
 use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use crate::error::Error;
+use crate::model::book::Book;
+use crate::model::clock_speed::{ClockSpeed, ClockSpeeds};
+use crate::model::rate::Rate;
 use crate::NotManual;
 use crate::recipe_filter::RecipeFilter;
 
-#[derive(serde::Deserialize,serde::Serialize, Debug)]
+#[derive(serde::Deserialize,serde::Serialize, Debug, Clone)]
 pub struct ProblemInput {
+    //the identifier of the book (see FullBook::identifier) this input has been written against
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub book: Option<String>,
+    //the amounts per minute to produce, or their ratios if maximize is set
     #[serde(rename="targets")]
-    pub target_items:HashMap<String,u32>,
+    pub target_items:HashMap<String,TargetAmount>,
     #[serde(rename="available-items")]
-    pub available_items:HashMap<String,u32>,
+    pub available_items:HashMap<String,Rate>,
     #[serde(rename="use-abundances")]
     pub use_abundances:bool,
-    pub filter:RecipeFilter
+    //the max amounts per minute extracted of some resources, by item id, like the nodes actually claimed
+    #[serde(rename="resource-caps", default, skip_serializing_if = "HashMap::is_empty")]
+    pub resource_caps:HashMap<String,Rate>,
+    //the cost of a unit of some resources, by item id, replacing their abundance weight
+    #[serde(rename="resource-weights", default, skip_serializing_if = "HashMap::is_empty")]
+    pub resource_weights:HashMap<String,f64>,
+    pub filter:RecipeFilter,
+    #[serde(default, skip_serializing_if = "PowerSetting::is_external")]
+    pub power:PowerSetting,
+    //the maximal power in MW consumed by the buildings and extractors, generators are not deducted
+    #[serde(rename="max-power", default, skip_serializing_if = "Option::is_none")]
+    pub max_power:Option<f64>,
+    #[serde(default, skip_serializing_if = "Objective::is_default")]
+    pub objective:Objective,
+    //the clock speed in percent of all the buildings (100 if not set)
+    #[serde(rename="clock-speed", default, skip_serializing_if = "Option::is_none")]
+    pub clock_speed:Option<f64>,
+    //the clock speed in percent of the buildings of some recipes, by recipe id
+    #[serde(rename="recipe-clock-speeds", default, skip_serializing_if = "HashMap::is_empty")]
+    pub recipe_clock_speeds:HashMap<String,f64>,
+    //solve with an integer number of buildings per recipe, underclocked to run its exact amount, instead of rounding them up afterwards
+    #[serde(rename="integer-buildings", default, skip_serializing_if = "std::ops::Not::not")]
+    pub integer_buildings:bool,
+    //produce as much as possible of the targets, in their ratios, with the resources and available items
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub maximize:bool,
+    //what becomes of the products that are neither targets nor consumed
+    #[serde(default, skip_serializing_if = "ByproductPolicy::is_allow")]
+    pub byproducts:ByproductPolicy,
+    //the policy of some byproducts, by item id, overriding the one of all the byproducts
+    #[serde(rename="byproduct-policies", default, skip_serializing_if = "HashMap::is_empty")]
+    pub byproduct_policies:HashMap<String,ByproductPolicy>,
+}
+
+/// The amount per minute of a target : an exact rate, or bounds like `{"min": 10, "max": "1/s"}`.
+/// Without a max, the target is produced at least at the min
+#[derive(serde::Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum TargetAmount {
+    Exact(Rate),
+    Range {
+        #[serde(default)]
+        min: Rate,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max: Option<Rate>,
+    },
+}
+
+impl TargetAmount {
+    pub fn bounds(&self) -> Bounds {
+        match self {
+            TargetAmount::Exact(amount) => Bounds { min: *amount, max: Some(*amount) },
+            TargetAmount::Range { min, max } => Bounds { min: *min, max: *max },
+        }
+    }
+
+    /// Exact amounts of the same target add up, a range replaces the previous amount
+    pub fn combine(self, other: TargetAmount) -> TargetAmount {
+        match (self, other) {
+            (TargetAmount::Exact(mut a1), TargetAmount::Exact(a2)) => {
+                a1 += a2;
+                TargetAmount::Exact(a1)
+            }
+            (_, other) => other,
+        }
+    }
+}
+
+/// Deserialize like the derived untagged enum, but reject the ranges whose min is above their max
+impl<'de> serde::Deserialize<'de> for TargetAmount {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(serde::Deserialize)]
+        #[serde(untagged)]
+        enum TargetAmountDto {
+            Exact(Rate),
+            Range {
+                #[serde(default)]
+                min: Rate,
+                #[serde(default)]
+                max: Option<Rate>,
+            },
+        }
+
+        match TargetAmountDto::deserialize(deserializer)? {
+            TargetAmountDto::Exact(amount) => Ok(TargetAmount::Exact(amount)),
+            TargetAmountDto::Range { min, max: Some(max) } if min > max =>
+                Err(serde::de::Error::custom(Error::TargetParsingFailed(format!("{}..{}", min, max)))),
+            TargetAmountDto::Range { min, max } => Ok(TargetAmount::Range { min, max }),
+        }
+    }
+}
+
+/// Parse `10` (exact), `10..15` (range) or `10+` (at least), each bound being a rate like `2/s`
+impl FromStr for TargetAmount {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(min) = s.strip_suffix('+') {
+            Ok(TargetAmount::Range { min: min.parse()?, max: None })
+        } else if let Some((min, max)) = s.split_once("..") {
+            let (min, max) = (min.parse::<Rate>()?, max.parse::<Rate>()?);
+            if min > max {
+                return Err(Error::TargetParsingFailed(s.to_string()));
+            }
+            Ok(TargetAmount::Range { min, max: Some(max) })
+        } else {
+            s.parse().map(TargetAmount::Exact)
+        }
+    }
+}
+
+/// The bounds of the amount per minute of a target
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bounds {
+    pub min: Rate,
+    pub max: Option<Rate>,
+}
+
+impl Bounds {
+    pub fn is_exact(&self) -> bool {
+        self.max == Some(self.min)
+    }
+
+    /// Whether an exact amount is within the bounds
+    pub fn contains(&self, amount: Rate) -> bool {
+        amount >= self.min && self.max.is_none_or(|max| amount <= max)
+    }
+
+    pub fn scaled(&self, factor: Rate) -> Bounds {
+        Bounds { min: self.min * factor, max: self.max.map(|m| m * factor) }
+    }
+}
+
+/// A quantity the solver can minimize
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Criterion {
+    //the resources used, weighted by their abundance if use-abundances is set
+    Resources,
+    //the number of buildings
+    Buildings,
+    //the power consumed by the buildings
+    Power,
+    //the items produced but not targeted
+    Waste,
+    //the number of distinct recipes used
+    Recipes,
+    //the power shards needed by the overclocked buildings
+    Shards,
+}
+
+impl Criterion {
+    pub const ALL: [Criterion; 6] = [Criterion::Resources, Criterion::Buildings, Criterion::Power, Criterion::Waste, Criterion::Recipes, Criterion::Shards];
+
+    pub fn id(&self) -> &'static str {
+        match self {
+            Criterion::Resources => "resources",
+            Criterion::Buildings => "buildings",
+            Criterion::Power => "power",
+            Criterion::Waste => "waste",
+            Criterion::Recipes => "recipes",
+            Criterion::Shards => "shards",
+        }
+    }
+}
+
+/// What the solver minimizes : a weighted sum of criteria like `resources+0.5*power`
+#[derive(serde::Deserialize,serde::Serialize, Debug, Clone, PartialEq)]
+#[serde(try_from = "String", into = "String")]
+pub struct Objective(Vec<(f64, Criterion)>);
+
+impl Objective {
+    pub fn single(criterion: Criterion) -> Self {
+        Objective(vec![(1f64, criterion)])
+    }
+
+    pub fn criteria(&self) -> &[(f64, Criterion)] {
+        &self.0
+    }
+
+    pub fn uses(&self, criterion: Criterion) -> bool {
+        self.0.iter().any(|(_, c)| *c == criterion)
+    }
+
+    pub fn is_default(&self) -> bool {
+        *self == Objective::default()
+    }
+}
+
+impl Default for Objective {
+    fn default() -> Self {
+        Objective::single(Criterion::Resources)
+    }
+}
+
+/// Parse criteria separated by `+`, each optionally preceded by a weight and `*`
+impl FromStr for Objective {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse_term = |term: &str| {
+            let (weight, id) = match term.split_once('*') {
+                Some((weight, id)) => (weight.trim().parse::<f64>().ok().filter(|w| *w >= 0f64)?, id.trim()),
+                None => (1f64, term.trim()),
+            };
+            Criterion::ALL.iter().find(|c| c.id() == id).map(|c| (weight, *c))
+        };
+
+        s.split('+')
+            .map(parse_term)
+            .collect::<Option<Vec<(f64, Criterion)>>>()
+            .map(Objective)
+            .ok_or_else(|| Error::ObjectiveParsingFailed(s.to_string()))
+    }
+}
+
+impl TryFrom<String> for Objective {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Objective> for String {
+    fn from(objective: Objective) -> Self {
+        objective.to_string()
+    }
+}
+
+impl Display for Objective {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let terms: Vec<String> = self.0.iter()
+            .map(|(weight, criterion)| if *weight == 1f64 { criterion.id().to_string() } else { format!("{}*{}", weight, criterion.id()) })
+            .collect();
+        f.write_str(&terms.join("+"))
+    }
+}
+
+/// What becomes of a byproduct : `allow` (a free leftover), `forbid` (no leftover at all),
+/// `penalize` with an optional cost per unit in the objective like `penalize:10`, or `sink`
+/// (the leftover goes to an AWESOME Sink, if the item has sink points)
+#[derive(serde::Deserialize,serde::Serialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(try_from = "String", into = "String")]
+pub enum ByproductPolicy {
+    #[default]
+    Allow,
+    Forbid,
+    Penalize(f64),
+    Sink,
+}
+
+impl ByproductPolicy {
+    pub fn is_allow(&self) -> bool {
+        *self == ByproductPolicy::Allow
+    }
+}
+
+impl FromStr for ByproductPolicy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "allow" => Ok(ByproductPolicy::Allow),
+            "forbid" => Ok(ByproductPolicy::Forbid),
+            "penalize" => Ok(ByproductPolicy::Penalize(1f64)),
+            "sink" => Ok(ByproductPolicy::Sink),
+            policy => policy.strip_prefix("penalize:")
+                .and_then(|cost| cost.trim().parse::<f64>().ok())
+                .filter(|cost| *cost >= 0f64)
+                .map(ByproductPolicy::Penalize)
+                .ok_or_else(|| Error::ByproductPolicyParsingFailed(s.to_string()))
+        }
+    }
+}
+
+impl TryFrom<String> for ByproductPolicy {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<ByproductPolicy> for String {
+    fn from(policy: ByproductPolicy) -> Self {
+        policy.to_string()
+    }
+}
+
+impl Display for ByproductPolicy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ByproductPolicy::Allow => f.write_str("allow"),
+            ByproductPolicy::Forbid => f.write_str("forbid"),
+            ByproductPolicy::Penalize(cost) if *cost == 1f64 => f.write_str("penalize"),
+            ByproductPolicy::Penalize(cost) => write!(f, "penalize:{}", cost),
+            ByproductPolicy::Sink => f.write_str("sink"),
+        }
+    }
+}
+
+/// How the power needed by the buildings is provided
+#[derive(serde::Deserialize,serde::Serialize, Debug, Clone, Copy, PartialEq, Default)]
+pub enum PowerSetting {
+    //the power comes from outside of the factory
+    #[default]
+    #[serde(rename="external")]
+    External,
+    //the generators of the factory must provide all the power it consumes
+    #[serde(rename="self-powered")]
+    SelfPowered,
+    //the power taken from the grid (consumption of the buildings and extractors minus generation) must not exceed the given MW
+    #[serde(rename="grid")]
+    Grid(f64),
+}
+
+impl PowerSetting {
+    pub fn is_external(&self) -> bool {
+        *self == PowerSetting::External
+    }
+}
+
+/// Parse `external`, `self-powered` or a grid budget in MW
+impl FromStr for PowerSetting {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "external" => Ok(PowerSetting::External),
+            "self-powered" | "self" => Ok(PowerSetting::SelfPowered),
+            budget => budget.parse::<f64>()
+                .ok()
+                .filter(|b| *b >= 0f64)
+                .map(PowerSetting::Grid)
+                .ok_or_else(|| Error::PowerParsingFailed(s.to_string()))
+        }
+    }
 }
 
 
 impl Default for ProblemInput {
     fn default() -> Self {
         ProblemInput{
+            book:None,
             target_items:HashMap::new(),
             available_items:HashMap::new(),
             use_abundances:true,
+            resource_caps:HashMap::new(),
+            resource_weights:HashMap::new(),
             filter:NotManual,
+            power:PowerSetting::External,
+            max_power:None,
+            objective:Objective::default(),
+            clock_speed:None,
+            recipe_clock_speeds:HashMap::new(),
+            integer_buildings:false,
+            maximize:false,
+            byproducts:ByproductPolicy::Allow,
+            byproduct_policies:HashMap::new(),
         }
     }
 }
 
 impl ProblemInput {
 
-    pub fn target_items(&self) -> &HashMap<String, u32> {
+    pub fn target_items(&self) -> &HashMap<String, TargetAmount> {
         &self.target_items
     }
 
-    pub fn available_items(&self) -> &HashMap<String,u32> {
+    pub fn available_items(&self) -> &HashMap<String,Rate> {
         &self.available_items
     }
 
@@ -39,5 +398,16 @@ impl ProblemInput {
         &self.filter
     }
 
+    /// The clock speeds of the input, the recipes being checked against the book
+    pub fn clock_speeds(&self, book: &dyn Book) -> crate::error::Result<ClockSpeeds> {
+        let default = self.clock_speed.map(ClockSpeed::new).transpose()?;
+        let by_recipe = self.recipe_clock_speeds.iter()
+            .map(|(recipe, percent)| book.get_recipe_by_id(recipe)
+                .and_then(|_| ClockSpeed::new(*percent))
+                .map(|c| (recipe.clone(), c)))
+            .collect::<crate::error::Result<HashMap<String, ClockSpeed>>>()?;
+        Ok(ClockSpeeds::new(default, by_recipe))
+    }
+
 }
 
@@ -44,8 +44,8 @@ impl Display for RecipeFilter {
             RecipeFilter::AllOf(_) => "all-of",
             RecipeFilter::AnyOf(_) => "any-of",
             RecipeFilter::Not(_) => "not",
-            RecipeFilter::NotNamed(_) => "not-named",
-            RecipeFilter::NotUsing(_) => "not-using"
+            RecipeFilter::NotNamed(recipe_name) => return write!(f, "wo_{}", recipe_name),
+            RecipeFilter::NotUsing(item_id) => return write!(f, "nu_{}", item_id),
         };
 
         f.write_str(name)
@@ -68,6 +68,15 @@ impl RecipeFilter {
             RecipeFilter::NotUsing(item_id) => !recipe.uses_item(item_id)
         }
     }
+
+    /// The filters, among this one and the ones it combines, that reject the recipe
+    pub fn rejecting(&self, recipe:&Recipe) -> Vec<&RecipeFilter> {
+        match self {
+            RecipeFilter::AllOf(filters) => filters.iter().flat_map(|f| f.rejecting(recipe)).collect(),
+            filter if filter.matches(recipe) => vec![],
+            filter => vec![filter],
+        }
+    }
 }
 
 impl FromStr for RecipeFilter {
@@ -0,0 +1,76 @@
+use crate::constants::is_nil;
+use crate::error::Result;
+use crate::factory::Factory;
+use crate::model::book::FilterableBook;
+use crate::model::full_book::FullBook;
+use crate::problem::Relaxation;
+use crate::problem_input::ProblemInput;
+
+/// The extra room given to a binding constraint to measure the change of the objective
+const EXTRA_ROOM: f64 = 1f64;
+
+/// A constraint reached by the solution, and the measured change of the objective per extra unit of it
+pub struct Bottleneck {
+    pub constraint: Relaxation,
+    pub objective_change: f64,
+}
+
+/// Find the binding constraints of the optimal solution. The solver gives no dual values, so
+/// the problem is solved again with some extra room for each one, and the change of the objective
+/// is divided by the extra amount the new solution actually uses. This finite difference is not a
+/// shadow price, it may differ when the extra room changes the basis of the solution. With the
+/// maximize mode, it is the change of the scale of the targets
+pub fn bottlenecks(input: &ProblemInput, full_book: &FullBook) -> Result<Vec<Bottleneck>> {
+    let book = full_book.filter(input.filter())?;
+    let optimum = Factory::create_problem(input, &book)?.optimum()?;
+    //the value of a maximized problem is the opposite of the scale
+    let sign = if input.maximize { -1f64 } else { 1f64 };
+
+    let mut bottlenecks = vec![];
+    for constraint in optimum.binding.clone() {
+        let loosened = Factory::create_problem(input, &book)?
+            .loosened(constraint.clone(), EXTRA_ROOM)
+            .optimum()?;
+        let step = loosened.usage(&constraint) - optimum.usage(&constraint);
+        let objective_change = if is_nil(step) { 0f64 } else { sign * (loosened.value - optimum.value) / step };
+        bottlenecks.push(Bottleneck { constraint, objective_change });
+    }
+
+    //the constraints that improve the objective the most first
+    bottlenecks.sort_by(|b1, b2| (sign * b1.objective_change).total_cmp(&(sign * b2.objective_change)));
+    Ok(bottlenecks)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use maplit::hashmap;
+
+    use crate::fixtures::{fixture_book, plates};
+    use crate::model::rate::Rate;
+    use crate::problem::Relaxation;
+    use crate::problem_input::{Criterion, Objective, ProblemInput};
+    use crate::sensitivity::bottlenecks;
+
+    #[test]
+    #[ignore = "needs the cbc solver"]
+    fn objective_change_per_unit() {
+        //below 30 ore, each extra ore replaces 2/5 refinery executions by 3/5 plate and 9/5 ingot
+        //executions : 1.92 MW saved, but 1/12 MW more for the miners and 1/500 for the tie break
+        let change = |cap: &str| {
+            let input = ProblemInput {
+                objective: Objective::single(Criterion::Power),
+                resource_caps: hashmap! {"iron_ore".to_string() => cap.parse::<Rate>().unwrap()},
+                ..plates("20")
+            };
+            let bottlenecks = bottlenecks(&input, &fixture_book()).unwrap();
+            assert_eq!(bottlenecks.len(), 1);
+            assert!(matches!(&bottlenecks[0].constraint, Relaxation::ResourceCap(item) if item.id() == "iron_ore"));
+            bottlenecks[0].objective_change
+        };
+        let expected = -1.92f64 + 1f64 / 12f64 + 1f64 / 500f64;
+        assert!((change("20") - expected).abs() < 1e-3);
+        //the ingots need 30 ore, only half of the extra room is used
+        assert!((change("29.5") - expected).abs() < 1e-3);
+    }
+}